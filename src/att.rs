@@ -0,0 +1,222 @@
+// AT&T FSM / OpenFst text format for acceptors: `src dst label [weight]` arc lines and
+// `state [weight]` final lines, where the source of the first line is the start state. States
+// are numbered from 0 without gaps. Weights are ignored when reading and never written.
+//
+// OpenFst reserves label 0 for epsilon, so symbol `n` is written as label `n + 1`. Symbol tables
+// follow the `name label` format of `fstcompile --isymbols`, with `<eps>` as label 0.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    error::Error,
+    io::{self, BufRead, Write},
+};
+
+use nicole::IdLike;
+
+use crate::{state_from_id, State, Symbol};
+
+const EPSILON_NAME: &str = "<eps>";
+
+fn symbol_from_label(label: i32) -> Result<Symbol, String> {
+    match label {
+        0 => Ok(Symbol::null()),
+        _ => label
+            .checked_sub(1)
+            .filter(|&x| x >= 0)
+            .map(Symbol)
+            .ok_or_else(|| format!("invalid label {}", label)),
+    }
+}
+
+fn label_from_symbol(symbol: Symbol) -> io::Result<i32> {
+    match symbol {
+        _ if symbol.is_null() => Ok(0),
+        Symbol(x) => x.checked_add(1).filter(|&x| x > 0).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("symbol {} has no label", symbol),
+            )
+        }),
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    names: BTreeMap<Symbol, String>,
+    symbols: HashMap<String, Symbol>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Names every symbol after its `Display` form.
+    pub fn from_symbols(symbols: impl IntoIterator<Item = Symbol>) -> Self {
+        let mut ret = Self::new();
+        for symbol in symbols {
+            if !symbol.is_null() {
+                ret.insert(symbol, symbol.to_string());
+            }
+        }
+        ret
+    }
+
+    pub fn insert(&mut self, symbol: Symbol, name: String) {
+        if let Some(old) = self.names.insert(symbol, name.clone()) {
+            self.symbols.remove(&old);
+        }
+        self.symbols.insert(name, symbol);
+    }
+
+    pub fn name(&self, symbol: Symbol) -> Option<&str> {
+        self.names.get(&symbol).map(String::as_str)
+    }
+
+    pub fn symbol(&self, name: &str) -> Option<Symbol> {
+        self.symbols.get(name).copied()
+    }
+
+    pub fn read(r: impl BufRead) -> Result<Self, Box<dyn Error>> {
+        let mut ret = Self::new();
+        for line in r.lines() {
+            let line = line?;
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                [] => {}
+                [name, label] => {
+                    let label: i32 = label.parse()?;
+                    if label != 0 {
+                        ret.insert(symbol_from_label(label)?, name.to_string());
+                    }
+                }
+                _ => return Err(format!("invalid symbol table line {:?}", line).into()),
+            }
+        }
+        Ok(ret)
+    }
+
+    pub fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "{}\t0", EPSILON_NAME)?;
+        for (&symbol, name) in self.names.iter() {
+            writeln!(w, "{}\t{}", name, label_from_symbol(symbol)?)?;
+        }
+        Ok(())
+    }
+}
+
+fn format_label(symbol: Symbol, symbols: Option<&SymbolTable>) -> io::Result<String> {
+    match symbols {
+        None => Ok(label_from_symbol(symbol)?.to_string()),
+        Some(_) if symbol.is_null() => Ok(EPSILON_NAME.to_string()),
+        Some(table) => table.name(symbol).map(str::to_string).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("symbol {} missing from the symbol table", symbol),
+            )
+        }),
+    }
+}
+
+fn parse_label(label: &str, symbols: Option<&SymbolTable>) -> Result<Symbol, Box<dyn Error>> {
+    match symbols {
+        Some(_) if label == EPSILON_NAME => Ok(Symbol::null()),
+        Some(table) => table
+            .symbol(label)
+            .ok_or_else(|| format!("label {:?} missing from the symbol table", label).into()),
+        None => Ok(symbol_from_label(label.parse()?)?),
+    }
+}
+
+pub(crate) fn write_att<W: Write>(
+    mut w: W,
+    start: State,
+    finals: &HashSet<State>,
+    edges: impl IntoIterator<Item = (State, Symbol, State)>,
+    symbols: Option<&SymbolTable>,
+) -> io::Result<()> {
+    let mut edges: Vec<(State, Symbol, State)> = edges.into_iter().collect();
+    // The start state must come first.
+    edges.sort_by_key(|&(state, symbol, next_state)| (state != start, state, symbol, next_state));
+
+    let start_has_edges = edges.first().is_some_and(|&(state, _, _)| state == start);
+    if !start_has_edges && !finals.contains(&start) {
+        // Nothing is reachable: write the empty automaton rather than a wrong start state.
+        return Ok(());
+    }
+
+    // Without edges from the start state, its final line must come first instead.
+    let mut finals: Vec<State> = finals.iter().copied().collect();
+    finals.sort_by_key(|&state| (state != start, state));
+    if !start_has_edges {
+        writeln!(w, "{}", usize::from(finals.remove(0)))?;
+    }
+
+    for (state, symbol, next_state) in edges {
+        writeln!(
+            w,
+            "{}\t{}\t{}",
+            usize::from(state),
+            usize::from(next_state),
+            format_label(symbol, symbols)?
+        )?;
+    }
+    for state in finals {
+        writeln!(w, "{}", usize::from(state))?;
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ParsedAtt {
+    pub state_count: usize,
+    pub start: Option<State>,
+    pub finals: HashSet<State>,
+    pub edges: Vec<(State, Symbol, State)>,
+}
+
+pub(crate) fn read_att(
+    r: impl BufRead,
+    symbols: Option<&SymbolTable>,
+) -> Result<ParsedAtt, Box<dyn Error>> {
+    let mut ret = ParsedAtt::default();
+    let mut mentioned: HashSet<State> = HashSet::new();
+
+    for line in r.lines() {
+        let line = line?;
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let state = match fields.first() {
+            Some(x) => state_from_id(x.parse::<usize>()?)?,
+            None => continue,
+        };
+        ret.start.get_or_insert(state);
+        ret.state_count = ret.state_count.max(usize::from(state) + 1);
+        mentioned.insert(state);
+
+        match fields[..] {
+            [_] | [_, _] => {
+                ret.finals.insert(state);
+            }
+            [_, next_state, label] | [_, next_state, label, _] => {
+                let next_state = state_from_id(next_state.parse::<usize>()?)?;
+                ret.state_count = ret.state_count.max(usize::from(next_state) + 1);
+                mentioned.insert(next_state);
+                ret.edges
+                    .push((state, parse_label(label, symbols)?, next_state));
+            }
+            _ => return Err(format!("invalid acceptor line {:?}", line).into()),
+        }
+    }
+
+    // As for DOT, the largest id alone must not size the automaton.
+    if ret.state_count > mentioned.len() {
+        let missing = (0..ret.state_count)
+            .map(State::from)
+            .find(|state| !mentioned.contains(state))
+            .unwrap();
+        return Err(format!("state {} is never mentioned", missing).into());
+    }
+
+    Ok(ret)
+}
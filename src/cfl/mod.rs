@@ -0,0 +1,173 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Debug, Display},
+    hash::Hash,
+};
+
+use nicole_derive::IdLike;
+
+use crate::{
+    graph::{LabeledGraph, ProductGraph},
+    State, Symbol, DFA,
+};
+
+#[derive(Default, Clone, Copy, Hash, Eq, IdLike, Ord, PartialEq, PartialOrd)]
+pub struct Nonterminal(i32);
+
+impl Display for Nonterminal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "N{}", self.0)
+    }
+}
+
+impl Debug for Nonterminal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "N{}", self.0)
+    }
+}
+
+// A grammar in Chomsky normal form. Epsilon rules are allowed for any nonterminal.
+#[derive(Clone, Debug, Default)]
+pub struct Grammar {
+    pub epsilon: HashSet<Nonterminal>,
+    pub terminals: Vec<(Nonterminal, Symbol)>,
+    pub binary: Vec<(Nonterminal, Nonterminal, Nonterminal)>,
+}
+
+impl Grammar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A -> ε
+    pub fn add_epsilon(&mut self, lhs: Nonterminal) -> &mut Self {
+        self.epsilon.insert(lhs);
+        self
+    }
+
+    // A -> a
+    pub fn add_terminal(&mut self, lhs: Nonterminal, symbol: Symbol) -> &mut Self {
+        self.terminals.push((lhs, symbol));
+        self
+    }
+
+    // A -> B C
+    pub fn add_binary(
+        &mut self,
+        lhs: Nonterminal,
+        left: Nonterminal,
+        right: Nonterminal,
+    ) -> &mut Self {
+        self.binary.push((lhs, left, right));
+        self
+    }
+}
+
+// All facts (u, A, v) such that some path from u to v spells a word derivable from A.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CflRelation<V: Eq + Hash> {
+    facts: HashSet<(V, Nonterminal, V)>,
+}
+
+impl<V: Copy + Eq + Hash> CflRelation<V> {
+    pub fn contains(&self, source: V, nonterminal: Nonterminal, target: V) -> bool {
+        self.facts.contains(&(source, nonterminal, target))
+    }
+
+    pub fn pairs(&self, nonterminal: Nonterminal) -> HashSet<(V, V)> {
+        self.facts
+            .iter()
+            .filter(|&&(_, x, _)| x == nonterminal)
+            .map(|&(source, _, target)| (source, target))
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (V, Nonterminal, V)> + '_ {
+        self.facts.iter().copied()
+    }
+}
+
+// Worklist CFL-reachability, cubic in the number of vertices.
+pub fn cfl_reachability<G: LabeledGraph>(
+    graph: &G,
+    vertices: impl IntoIterator<Item = G::Vertex>,
+    grammar: &Grammar,
+) -> CflRelation<G::Vertex> {
+    let mut by_terminal: HashMap<Symbol, Vec<Nonterminal>> = HashMap::new();
+    for &(lhs, symbol) in grammar.terminals.iter() {
+        by_terminal.entry(symbol).or_default().push(lhs);
+    }
+    let mut by_left: HashMap<Nonterminal, Vec<(Nonterminal, Nonterminal)>> = HashMap::new();
+    let mut by_right: HashMap<Nonterminal, Vec<(Nonterminal, Nonterminal)>> = HashMap::new();
+    for &(lhs, left, right) in grammar.binary.iter() {
+        by_left.entry(left).or_default().push((lhs, right));
+        by_right.entry(right).or_default().push((lhs, left));
+    }
+
+    let mut facts: HashSet<(G::Vertex, Nonterminal, G::Vertex)> = HashSet::new();
+    let mut outgoing: HashMap<(G::Vertex, Nonterminal), Vec<G::Vertex>> = HashMap::new();
+    let mut incoming: HashMap<(G::Vertex, Nonterminal), Vec<G::Vertex>> = HashMap::new();
+    let mut worklist: Vec<(G::Vertex, Nonterminal, G::Vertex)> = Vec::new();
+
+    let mut add = |fact: (G::Vertex, Nonterminal, G::Vertex), worklist: &mut Vec<_>| {
+        if facts.insert(fact) {
+            worklist.push(fact);
+        }
+    };
+
+    for vertex in vertices {
+        for &lhs in grammar.epsilon.iter() {
+            add((vertex, lhs, vertex), &mut worklist);
+        }
+        for (symbol, next_vertex) in graph.successors(vertex) {
+            for &lhs in by_terminal.get(&symbol).into_iter().flatten() {
+                add((vertex, lhs, next_vertex), &mut worklist);
+            }
+        }
+    }
+
+    while let Some((u, nonterminal, v)) = worklist.pop() {
+        outgoing.entry((u, nonterminal)).or_default().push(v);
+        incoming.entry((v, nonterminal)).or_default().push(u);
+
+        // A -> nonterminal C: (u, nonterminal, v) + (v, C, w) => (u, A, w)
+        for &(lhs, right) in by_left.get(&nonterminal).into_iter().flatten() {
+            for &w in outgoing.get(&(v, right)).into_iter().flatten() {
+                add((u, lhs, w), &mut worklist);
+            }
+        }
+        // A -> C nonterminal: (w, C, u) + (u, nonterminal, v) => (w, A, v)
+        for &(lhs, left) in by_right.get(&nonterminal).into_iter().flatten() {
+            for &w in incoming.get(&(u, left)).into_iter().flatten() {
+                add((w, lhs, v), &mut worklist);
+            }
+        }
+    }
+
+    CflRelation { facts }
+}
+
+// CFL-reachability restricted to paths whose labels are also accepted by the DFA. This is the
+// Bar-Hillel construction: the grammar is evaluated over the product of the graph and the DFA,
+// and a fact ((u, initial), A, (v, final)) of the product becomes the fact (u, A, v).
+pub fn cfl_reachability_with_dfa<G: LabeledGraph>(
+    graph: &G,
+    vertices: impl IntoIterator<Item = G::Vertex>,
+    grammar: &Grammar,
+    dfa: &DFA,
+) -> CflRelation<G::Vertex> {
+    let product = ProductGraph::new(graph, dfa);
+    let product_vertices: Vec<(G::Vertex, State)> = vertices
+        .into_iter()
+        .flat_map(|vertex| (0..dfa.next.len()).map(move |state| (vertex, state.into())))
+        .collect();
+
+    let facts = cfl_reachability(&product, product_vertices, grammar)
+        .facts
+        .into_iter()
+        .filter(|&((_, p), _, (_, q))| p == dfa.initial && dfa.finals.contains(&q))
+        .map(|((u, _), nonterminal, (v, _))| (u, nonterminal, v))
+        .collect();
+
+    CflRelation { facts }
+}
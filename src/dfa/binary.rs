@@ -0,0 +1,332 @@
+// Compact binary format for DFAs. All integers are little-endian u32 (symbols are i32):
+//
+//     magic "FSAD", version: u16, layout: u16 (0 = dense, 1 = sparse)
+//     state count, initial state, alphabet size, transition count
+//     alphabet: sorted symbols
+//     finals: bitset of ceil(state count / 32) words
+//     dense:  state count * alphabet size targets, DEAD for a missing transition
+//     sparse: state count + 1 offsets into the entries, then (symbol index, target) entries sorted
+//             by symbol index within each state
+//
+// `DfaRef` runs directly over such a buffer, without any alignment requirement.
+
+use std::{collections::HashSet, error::Error};
+
+use crate::{State, Symbol, DFA};
+
+const MAGIC: &[u8; 4] = b"FSAD";
+const VERSION: u16 = 1;
+const HEADER_LEN: usize = 24;
+const DEAD: u32 = u32::MAX;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryLayout {
+    Dense,
+    Sparse,
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn word_count(state_count: usize) -> usize {
+    state_count.div_ceil(32)
+}
+
+pub fn to_bytes(dfa: &DFA, layout: BinaryLayout) -> Vec<u8> {
+    let mut alphabet: Vec<Symbol> = dfa
+        .next
+        .iter()
+        .flat_map(|(_, dict)| dict.keys().copied())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    alphabet.sort();
+    let index = |symbol: &Symbol| alphabet.binary_search(symbol).unwrap() as u32;
+
+    let state_count = dfa.next.len();
+    let transition_count: usize = dfa.next.iter().map(|(_, dict)| dict.len()).sum();
+
+    let mut ret = Vec::new();
+    ret.extend_from_slice(MAGIC);
+    ret.extend_from_slice(&VERSION.to_le_bytes());
+    let layout_id: u16 = match layout {
+        BinaryLayout::Dense => 0,
+        BinaryLayout::Sparse => 1,
+    };
+    ret.extend_from_slice(&layout_id.to_le_bytes());
+    for x in [
+        state_count,
+        dfa.initial.into(),
+        alphabet.len(),
+        transition_count,
+    ] {
+        ret.extend_from_slice(&(x as u32).to_le_bytes());
+    }
+    for symbol in alphabet.iter() {
+        ret.extend_from_slice(&symbol.0.to_le_bytes());
+    }
+
+    let mut words = vec![0u32; word_count(state_count)];
+    for &state in dfa.finals.iter() {
+        words[usize::from(state) / 32] |= 1 << (usize::from(state) % 32);
+    }
+    for word in words {
+        ret.extend_from_slice(&word.to_le_bytes());
+    }
+
+    match layout {
+        BinaryLayout::Dense => {
+            for (_, dict) in dfa.next.iter() {
+                let mut row = vec![DEAD; alphabet.len()];
+                for (symbol, &next_state) in dict.iter() {
+                    row[index(symbol) as usize] = usize::from(next_state) as u32;
+                }
+                for x in row {
+                    ret.extend_from_slice(&x.to_le_bytes());
+                }
+            }
+        }
+        BinaryLayout::Sparse => {
+            let mut offset = 0;
+            for (_, dict) in dfa.next.iter() {
+                ret.extend_from_slice(&(offset as u32).to_le_bytes());
+                offset += dict.len();
+            }
+            ret.extend_from_slice(&(offset as u32).to_le_bytes());
+
+            for (_, dict) in dfa.next.iter() {
+                let mut row: Vec<(u32, u32)> = dict
+                    .iter()
+                    .map(|(symbol, &next_state)| (index(symbol), usize::from(next_state) as u32))
+                    .collect();
+                row.sort();
+                for (symbol, next_state) in row {
+                    ret.extend_from_slice(&symbol.to_le_bytes());
+                    ret.extend_from_slice(&next_state.to_le_bytes());
+                }
+            }
+        }
+    }
+
+    ret
+}
+
+// A read-only DFA over a borrowed buffer in the binary format.
+#[derive(Clone, Copy, Debug)]
+pub struct DfaRef<'a> {
+    bytes: &'a [u8],
+    layout: BinaryLayout,
+    state_count: usize,
+    initial: State,
+    alphabet_len: usize,
+    transition_count: usize,
+    finals_offset: usize,
+    table_offset: usize,
+}
+
+impl<'a> DfaRef<'a> {
+    // Validates the whole buffer, so that later lookups never fail.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, Box<dyn Error>> {
+        if bytes.len() < HEADER_LEN || &bytes[0..4] != MAGIC {
+            return Err("not a binary DFA".into());
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != VERSION {
+            return Err(format!("unsupported binary DFA version {}", version).into());
+        }
+        let layout = match u16::from_le_bytes([bytes[6], bytes[7]]) {
+            0 => BinaryLayout::Dense,
+            1 => BinaryLayout::Sparse,
+            x => return Err(format!("unknown binary DFA layout {}", x).into()),
+        };
+
+        let state_count = read_u32(bytes, 8) as usize;
+        let initial = read_u32(bytes, 12) as usize;
+        let alphabet_len = read_u32(bytes, 16) as usize;
+        let transition_count = read_u32(bytes, 20) as usize;
+        if state_count > i32::MAX as usize {
+            return Err("too many states".into());
+        }
+        if initial >= state_count {
+            return Err("initial state out of range".into());
+        }
+
+        // The header is untrusted, so sizes are computed with checked arithmetic.
+        let table_len = match layout {
+            BinaryLayout::Dense => state_count.checked_mul(alphabet_len),
+            BinaryLayout::Sparse => transition_count
+                .checked_mul(2)
+                .and_then(|x| x.checked_add(state_count + 1)),
+        };
+        let finals_offset = alphabet_len
+            .checked_mul(4)
+            .and_then(|x| x.checked_add(HEADER_LEN));
+        let table_offset = finals_offset.and_then(|x| x.checked_add(4 * word_count(state_count)));
+        let len = table_len
+            .and_then(|x| x.checked_mul(4))
+            .zip(table_offset)
+            .and_then(|(x, y)| x.checked_add(y));
+        let (Some(finals_offset), Some(table_offset), Some(len)) =
+            (finals_offset, table_offset, len)
+        else {
+            return Err("binary DFA sizes overflow".into());
+        };
+        if bytes.len() != len {
+            return Err("binary DFA has the wrong length".into());
+        }
+        let table_len = table_len.unwrap();
+
+        let ret = Self {
+            bytes,
+            layout,
+            state_count,
+            initial: State::from(initial),
+            alphabet_len,
+            transition_count,
+            finals_offset,
+            table_offset,
+        };
+
+        if (1..ret.alphabet_len).any(|i| ret.symbol(i - 1) >= ret.symbol(i)) {
+            return Err("alphabet is not sorted".into());
+        }
+        // Epsilon is stored as a negative symbol, and a DFA cannot have transitions on it.
+        if (0..ret.alphabet_len).any(|i| ret.symbol(i).0 < 0) {
+            return Err("alphabet contains a negative symbol".into());
+        }
+
+        let check_target = |target: u32| target == DEAD || (target as usize) < ret.state_count;
+        match layout {
+            BinaryLayout::Dense => {
+                if !(0..table_len).all(|i| check_target(ret.table(i))) {
+                    return Err("transition target out of range".into());
+                }
+            }
+            BinaryLayout::Sparse => {
+                let entries = ret.state_count + 1;
+                for state in 0..ret.state_count {
+                    let (start, end) = (ret.table(state) as usize, ret.table(state + 1) as usize);
+                    if start > end || end > ret.transition_count {
+                        return Err("transition offsets out of range".into());
+                    }
+                    for entry in start..end {
+                        let symbol = ret.table(entries + 2 * entry);
+                        let target = ret.table(entries + 2 * entry + 1);
+                        if symbol as usize >= ret.alphabet_len || target as usize >= ret.state_count
+                        {
+                            return Err("transition out of range".into());
+                        }
+                        if entry > start && ret.table(entries + 2 * entry - 2) >= symbol {
+                            return Err("transitions are not sorted".into());
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(ret)
+    }
+
+    fn symbol(&self, index: usize) -> Symbol {
+        Symbol(read_u32(self.bytes, HEADER_LEN + 4 * index) as i32)
+    }
+
+    fn table(&self, index: usize) -> u32 {
+        read_u32(self.bytes, self.table_offset + 4 * index)
+    }
+
+    fn symbol_index(&self, symbol: Symbol) -> Option<usize> {
+        let (mut low, mut high) = (0, self.alphabet_len);
+        while low < high {
+            let mid = (low + high) / 2;
+            match self.symbol(mid).cmp(&symbol) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Some(mid),
+            }
+        }
+        None
+    }
+
+    pub fn layout(&self) -> BinaryLayout {
+        self.layout
+    }
+
+    pub fn state_count(&self) -> usize {
+        self.state_count
+    }
+
+    pub fn initial(&self) -> State {
+        self.initial
+    }
+
+    pub fn is_final(&self, state: State) -> bool {
+        let state = usize::from(state);
+        read_u32(self.bytes, self.finals_offset + 4 * (state / 32)) & (1 << (state % 32)) != 0
+    }
+
+    pub fn next(&self, state: State, symbol: Symbol) -> Option<State> {
+        let index = self.symbol_index(symbol)?;
+        let state = usize::from(state);
+
+        let target = match self.layout {
+            BinaryLayout::Dense => self.table(state * self.alphabet_len + index),
+            BinaryLayout::Sparse => {
+                let entries = self.state_count + 1;
+                let (mut low, mut high) =
+                    (self.table(state) as usize, self.table(state + 1) as usize);
+                loop {
+                    if low >= high {
+                        return None;
+                    }
+                    let mid = (low + high) / 2;
+                    let x = self.table(entries + 2 * mid) as usize;
+                    if x < index {
+                        low = mid + 1;
+                    } else if x > index {
+                        high = mid;
+                    } else {
+                        break self.table(entries + 2 * mid + 1);
+                    }
+                }
+            }
+        };
+
+        (target != DEAD).then(|| State::from(target as usize))
+    }
+
+    pub fn matches(&self, string: &[Symbol]) -> bool {
+        let mut state = self.initial;
+
+        for &symbol in string {
+            match self.next(state, symbol) {
+                Some(next_state) => state = next_state,
+                None => return false,
+            }
+        }
+
+        self.is_final(state)
+    }
+
+    pub fn to_dfa(&self) -> Result<DFA, Box<dyn Error>> {
+        let mut transitions = Vec::new();
+        for state in (0..self.state_count).map(State::from) {
+            for index in 0..self.alphabet_len {
+                if let Some(next_state) = self.next(state, self.symbol(index)) {
+                    transitions.push((state, self.symbol(index), next_state));
+                }
+            }
+        }
+        let finals = (0..self.state_count)
+            .map(State::from)
+            .filter(|&x| self.is_final(x));
+
+        Ok(DFA::from_transitions(
+            self.state_count,
+            self.initial,
+            finals,
+            transitions,
+        )?)
+    }
+}
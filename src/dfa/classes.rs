@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use crate::{State, Symbol};
+
+use super::NextElems;
+
+// A partition of the alphabet into classes of symbols that have the same transitions in every
+// state. Class ids are symbols numbered from 0, in order of their smallest member.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolClasses {
+    classes: HashMap<Symbol, Symbol>,
+    members: Vec<Vec<Symbol>>,
+}
+
+pub fn symbol_classes(next: &NextElems) -> SymbolClasses {
+    let mut signatures: HashMap<Symbol, Vec<Option<State>>> = HashMap::new();
+    for (state, dict) in next.iter() {
+        for (&symbol, &next_state) in dict.iter() {
+            signatures
+                .entry(symbol)
+                .or_insert_with(|| vec![None; next.len()])[usize::from(state)] = Some(next_state);
+        }
+    }
+
+    let mut symbols: Vec<Symbol> = signatures.keys().copied().collect();
+    symbols.sort();
+
+    let mut class_ids: HashMap<&[Option<State>], usize> = HashMap::new();
+    let mut ret = SymbolClasses {
+        classes: HashMap::new(),
+        members: Vec::new(),
+    };
+    for symbol in symbols {
+        let class_count = class_ids.len();
+        let class = *class_ids.entry(&signatures[&symbol]).or_insert(class_count);
+        if class == ret.members.len() {
+            ret.members.push(Vec::new());
+        }
+        ret.members[class].push(symbol);
+        ret.classes.insert(symbol, Symbol(class as i32));
+    }
+
+    ret
+}
+
+impl SymbolClasses {
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    // Returns `None` for symbols outside the alphabet.
+    pub fn class(&self, symbol: Symbol) -> Option<Symbol> {
+        self.classes.get(&symbol).copied()
+    }
+
+    pub fn members(&self, class: Symbol) -> &[Symbol] {
+        &self.members[class.0 as usize]
+    }
+
+    pub fn translate(&self, string: &[Symbol]) -> Option<Vec<Symbol>> {
+        string.iter().map(|&symbol| self.class(symbol)).collect()
+    }
+
+    // Rewrites the transitions to use class ids. Since every member of a class has the same
+    // transitions, one transition per class is enough.
+    pub fn compress(&self, next: &NextElems) -> NextElems {
+        let mut ret = NextElems::new();
+        *ret = next
+            .iter()
+            .map(|(_, dict)| {
+                dict.iter()
+                    .map(|(&symbol, &next_state)| (self.classes[&symbol], next_state))
+                    .collect()
+            })
+            .collect();
+        ret
+    }
+
+    // The inverse of `compress`.
+    pub fn expand(&self, next: &NextElems) -> NextElems {
+        let mut ret = NextElems::new();
+        *ret = next
+            .iter()
+            .map(|(_, dict)| {
+                dict.iter()
+                    .flat_map(|(&class, &next_state)| {
+                        self.members(class)
+                            .iter()
+                            .map(move |&symbol| (symbol, next_state))
+                    })
+                    .collect()
+            })
+            .collect();
+        ret
+    }
+}
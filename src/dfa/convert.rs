@@ -2,7 +2,7 @@ use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 
 use nicole::{typedvec::TypedVec, IdLike};
 
-use crate::{dfa, nfa, State, Symbol, DFA, NFA};
+use crate::{dfa, nfa, LimitError, Limits, State, Symbol, DFA, NFA};
 
 pub fn e_closure(nfa: &nfa::NextElems) -> TypedVec<State, BTreeSet<State>> {
     let mut ret: TypedVec<State, BTreeSet<State>> = TypedVec::new();
@@ -18,7 +18,7 @@ pub fn e_closure(nfa: &nfa::NextElems) -> TypedVec<State, BTreeSet<State>> {
 
         while let Some(x) = stack.pop() {
             if !visited[x] {
-                ret[initial_state].insert(x.into());
+                ret[initial_state].insert(x);
                 if let Some(neighbors) = nfa[x].get(&Symbol::null()) {
                     stack.extend(neighbors);
                 }
@@ -30,7 +30,25 @@ pub fn e_closure(nfa: &nfa::NextElems) -> TypedVec<State, BTreeSet<State>> {
     ret
 }
 
-pub fn nfa_to_dfa(nfa: NFA) -> DFA {
+pub fn nfa_to_dfa(nfa: &NFA) -> DFA {
+    try_nfa_to_dfa(nfa, Limits::UNLIMITED).unwrap()
+}
+
+pub fn try_nfa_to_dfa(nfa: &NFA, limits: Limits) -> Result<DFA, LimitError> {
+    determinize(nfa, limits, |_, _| {})
+}
+
+// The subset construction. `visit` is called with every DFA state and the NFA states making it
+// up, so callers needing the subsets need not keep them all.
+pub fn determinize(
+    nfa: &NFA,
+    limits: Limits,
+    mut visit: impl FnMut(State, &BTreeSet<State>),
+) -> Result<DFA, LimitError> {
+    if limits.max_states == 0 {
+        return Err(LimitError::TooManyStates { limit: 0 });
+    }
+
     let mut queue: VecDeque<BTreeSet<State>> = VecDeque::new();
     let mut translate: HashMap<BTreeSet<State>, State> = HashMap::new();
     let mut ret = dfa::NextElems::new();
@@ -46,13 +64,15 @@ pub fn nfa_to_dfa(nfa: NFA) -> DFA {
     queue.push_back(initial.clone());
     translate.insert(initial, 0.into());
     let mut max_state = 1;
+    let mut transitions = 0;
 
     while let Some(vec) = queue.pop_front() {
         let mut ret2 = dfa::NextElem::new();
 
-        if vec.iter().any(|x| nfa.finals.contains(&x)) {
+        if vec.iter().any(|x| nfa.finals.contains(x)) {
             finals.insert(translate[&vec]);
         }
+        visit(translate[&vec], &vec);
 
         let mut next_states = nfa::NextElem::new();
         for state in vec {
@@ -67,22 +87,36 @@ pub fn nfa_to_dfa(nfa: NFA) -> DFA {
         }
 
         for (symbol, next) in next_states.into_iter() {
-            let next_state_id = translate.get(&next).copied().unwrap_or_else(|| {
-                queue.push_back(next.clone());
-                translate.insert(next.clone(), max_state.into());
-                max_state += 1;
-                (max_state - 1).into()
-            });
+            let next_state_id = match translate.get(&next) {
+                Some(&x) => x,
+                None => {
+                    if max_state >= limits.max_states {
+                        return Err(LimitError::TooManyStates {
+                            limit: limits.max_states,
+                        });
+                    }
+                    queue.push_back(next.clone());
+                    translate.insert(next, max_state.into());
+                    max_state += 1;
+                    (max_state - 1).into()
+                }
+            };
 
+            transitions += 1;
+            if transitions > limits.max_transitions {
+                return Err(LimitError::TooManyTransitions {
+                    limit: limits.max_transitions,
+                });
+            }
             ret2.insert(symbol, next_state_id);
         }
 
         ret.push(ret2);
     }
 
-    DFA {
+    Ok(DFA {
         next: ret,
         initial: State(0),
         finals,
-    }
+    })
 }
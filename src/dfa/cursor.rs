@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use nicole::typedvec::TypedVec;
+
+use crate::{State, Symbol, DFA};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StepResult {
+    // The DFA is in a non-final state that has not died yet.
+    Alive,
+    // No continuation can be accepted anymore; further steps keep returning `Dead`.
+    Dead,
+    Accepting,
+}
+
+// The states from which a final state can still be reached.
+pub(crate) fn live_states(
+    state_count: usize,
+    finals: impl IntoIterator<Item = State>,
+    edges: impl IntoIterator<Item = (State, State)>,
+) -> TypedVec<State, bool> {
+    let mut prev: HashMap<State, Vec<State>> = HashMap::new();
+    for (state, next_state) in edges {
+        prev.entry(next_state).or_default().push(state);
+    }
+
+    let mut live: TypedVec<State, bool> = TypedVec::new();
+    live.resize(state_count, false);
+    let mut stack: Vec<State> = finals.into_iter().collect();
+    for &state in stack.iter() {
+        live[state] = true;
+    }
+    while let Some(state) = stack.pop() {
+        for &prev_state in prev.get(&state).into_iter().flatten() {
+            if !live[prev_state] {
+                live[prev_state] = true;
+                stack.push(prev_state);
+            }
+        }
+    }
+
+    live
+}
+
+// A DFA along with its live states, computed once so that cursors are cheap to start and clone.
+#[derive(Clone, Debug)]
+pub struct LiveDFA<'a> {
+    dfa: &'a DFA,
+    live: TypedVec<State, bool>,
+}
+
+impl<'a> LiveDFA<'a> {
+    pub fn new(dfa: &'a DFA) -> Self {
+        let edges = dfa
+            .next
+            .iter()
+            .flat_map(|(state, dict)| dict.values().map(move |&next_state| (state, next_state)));
+
+        Self {
+            dfa,
+            live: live_states(dfa.next.len(), dfa.finals.iter().copied(), edges),
+        }
+    }
+
+    pub fn is_live(&self, state: State) -> bool {
+        self.live[state]
+    }
+
+    pub fn start(&self) -> Cursor<'_> {
+        let mut ret = Cursor {
+            dfa: self.dfa,
+            live: &self.live,
+            state: None,
+        };
+        ret.reset();
+        ret
+    }
+}
+
+// Runs a DFA one symbol at a time. The cursor dies as soon as it enters a state from which no
+// final state is reachable, such as an explicit sink state.
+#[derive(Clone, Debug)]
+pub struct Cursor<'a> {
+    dfa: &'a DFA,
+    live: &'a TypedVec<State, bool>,
+    state: Option<State>,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn step(&mut self, symbol: Symbol) -> StepResult {
+        self.state = self
+            .state
+            .and_then(|state| self.dfa.next[state].get(&symbol).copied())
+            .filter(|&state| self.live[state]);
+
+        match self.state {
+            None => StepResult::Dead,
+            Some(_) if self.is_accepting() => StepResult::Accepting,
+            Some(_) => StepResult::Alive,
+        }
+    }
+
+    pub fn is_accepting(&self) -> bool {
+        self.state
+            .is_some_and(|state| self.dfa.finals.contains(&state))
+    }
+
+    // Returns `None` once the DFA has died.
+    pub fn state(&self) -> Option<State> {
+        self.state
+    }
+
+    pub fn reset(&mut self) {
+        self.state = Some(self.dfa.initial).filter(|&state| self.live[state]);
+    }
+}
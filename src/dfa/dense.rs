@@ -0,0 +1,148 @@
+use crate::{State, Symbol, DFA};
+
+use super::classes::symbol_classes;
+
+// Symbol spans up to this size are mapped to classes with a direct lookup table.
+const MAX_TABLE_SPAN: usize = 1 << 16;
+
+#[derive(Clone, Debug)]
+enum ClassMap {
+    Table { offset: i32, classes: Vec<u32> },
+    Sorted(Vec<(Symbol, u32)>),
+}
+
+impl ClassMap {
+    fn get(&self, symbol: Symbol) -> u32 {
+        match self {
+            ClassMap::Table { offset, classes } => classes
+                .get(symbol.0.wrapping_sub(*offset) as u32 as usize)
+                .copied()
+                .unwrap_or(0),
+            ClassMap::Sorted(classes) => classes
+                .binary_search_by_key(&symbol, |&(x, _)| x)
+                .map_or(0, |i| classes[i].1),
+        }
+    }
+}
+
+// A DFA with a flat transition table indexed by state and symbol class. States keep their numbers
+// from the original DFA, and an extra dead state loops to itself on every class. Row offsets are
+// stored as u32, so converting a DFA whose table would reach 2^32 entries panics.
+#[derive(Clone, Debug)]
+pub struct DenseDFA {
+    classes: ClassMap,
+    stride: usize,
+    // Transition targets are stored premultiplied by the stride, i.e. as row offsets.
+    table: Vec<u32>,
+    finals: Vec<bool>,
+    initial: u32,
+    dead: u32,
+}
+
+impl From<&DFA> for DenseDFA {
+    fn from(dfa: &DFA) -> Self {
+        // Class 0 holds every symbol outside the alphabet, which always leads to the dead state.
+        let symbol_classes = symbol_classes(&dfa.next);
+        let stride = symbol_classes.len() + 1;
+        let state_count = dfa.next.len();
+        // The dead row has the largest offset, so every other offset fits once it does.
+        let dead = state_count
+            .checked_mul(stride)
+            .and_then(|x| u32::try_from(x).ok())
+            .expect("DenseDFA table has 2^32 entries or more");
+
+        let mut table = vec![dead; (state_count + 1) * stride];
+        for (state, dict) in symbol_classes.compress(&dfa.next).iter() {
+            let row = usize::from(state) * stride;
+            for (class, &next_state) in dict.iter() {
+                table[row + class.0 as usize + 1] = (usize::from(next_state) * stride) as u32;
+            }
+        }
+
+        let mut finals = vec![false; state_count + 1];
+        for &state in dfa.finals.iter() {
+            finals[usize::from(state)] = true;
+        }
+
+        let mut classes: Vec<(Symbol, u32)> = (0..symbol_classes.len())
+            .flat_map(|class| {
+                symbol_classes
+                    .members(Symbol(class as i32))
+                    .iter()
+                    .map(move |&symbol| (symbol, class as u32 + 1))
+            })
+            .collect();
+        classes.sort();
+
+        let classes = match (classes.first(), classes.last()) {
+            (Some(&(first, _)), Some(&(last, _)))
+                if ((last.0 as i64 - first.0 as i64) as usize) < MAX_TABLE_SPAN =>
+            {
+                let mut table = vec![0; (last.0 - first.0) as usize + 1];
+                for &(symbol, class) in classes.iter() {
+                    table[(symbol.0 - first.0) as usize] = class;
+                }
+                ClassMap::Table {
+                    offset: first.0,
+                    classes: table,
+                }
+            }
+            _ => ClassMap::Sorted(classes),
+        };
+
+        Self {
+            classes,
+            stride,
+            table,
+            finals,
+            initial: (usize::from(dfa.initial) * stride) as u32,
+            dead,
+        }
+    }
+}
+
+impl DenseDFA {
+    pub fn state_count(&self) -> usize {
+        self.finals.len() - 1
+    }
+
+    pub fn class_count(&self) -> usize {
+        self.stride
+    }
+
+    pub fn class(&self, symbol: Symbol) -> u32 {
+        self.classes.get(symbol)
+    }
+
+    // Returns the state reached after reading the whole string, or `None` if the DFA died.
+    pub fn run(&self, string: &[Symbol]) -> Option<State> {
+        self.run_iter(string.iter().copied())
+    }
+
+    pub fn matches(&self, string: &[Symbol]) -> bool {
+        self.run(string)
+            .is_some_and(|state| self.finals[usize::from(state)])
+    }
+
+    pub fn matches_bytes(&self, bytes: &[u8]) -> bool {
+        self.run_iter(bytes.iter().copied().map(Symbol::from_byte))
+            .is_some_and(|state| self.finals[usize::from(state)])
+    }
+
+    pub fn matches_str(&self, s: &str) -> bool {
+        self.matches_bytes(s.as_bytes())
+    }
+
+    fn run_iter(&self, string: impl IntoIterator<Item = Symbol>) -> Option<State> {
+        let mut row = self.initial;
+
+        for symbol in string {
+            row = self.table[row as usize + self.classes.get(symbol) as usize];
+            if row == self.dead {
+                return None;
+            }
+        }
+
+        Some(State::from(row as usize / self.stride))
+    }
+}
@@ -35,7 +35,7 @@ fn dfs(dfa: &DFA, end: State, visited: &mut HashSet<State>, current: State) -> V
 }
 
 fn find_paths(dfa: &DFA, start: State, end: State) -> Vec<StateString> {
-    let mut paths = dfs(&dfa, end, &mut HashSet::new(), start);
+    let mut paths = dfs(dfa, end, &mut HashSet::new(), start);
 
     for path in paths.iter_mut() {
         path.0.reverse();
@@ -62,22 +62,20 @@ impl super::DFA {
             .finals
             .iter()
             .copied()
-            .flat_map(|final_state| find_paths(&self, self.initial, final_state))
+            .flat_map(|final_state| find_paths(self, self.initial, final_state))
             .collect();
         let mut cycles: HashMap<State, Vec<StateString>> = HashMap::new();
         let mut visited: HashSet<StateString> = HashSet::new();
 
         for i in 0..self.next.len() {
             let state = State::from(i);
-            let mut c = find_paths(&self, state, state);
+            let mut c = find_paths(self, state, state);
             c.retain(|elem| !elem.0.is_empty());
             cycles.insert(state, c);
         }
 
-        if self.finals.contains(&self.initial) {
-            if !f(&[]) {
-                return;
-            }
+        if self.finals.contains(&self.initial) && !f(&[]) {
+            return;
         }
 
         while let Some(state_string) = queue.pop_front() {
@@ -0,0 +1,134 @@
+use std::collections::{BTreeSet, HashMap};
+
+use nicole::typedvec::TypedVec;
+
+use crate::{State, Symbol, NFA};
+
+use super::convert::e_closure;
+
+#[derive(Clone, Debug)]
+struct CachedState {
+    subset: BTreeSet<State>,
+    is_final: bool,
+    // `None` caches a transition to the empty subset.
+    next: HashMap<Symbol, Option<State>>,
+}
+
+// Determinizes an NFA subset by subset as input demands, like `nfa_to_dfa`. At most `capacity`
+// subsets are cached; when the cache is full it is cleared entirely, so memory stays bounded at
+// the cost of recomputing the subsets that are visited again.
+#[derive(Clone, Debug)]
+pub struct LazyDFA {
+    nfa: NFA,
+    closures: TypedVec<State, BTreeSet<State>>,
+    initial: BTreeSet<State>,
+    capacity: usize,
+    states: TypedVec<State, CachedState>,
+    translate: HashMap<BTreeSet<State>, State>,
+    evictions: usize,
+}
+
+impl From<NFA> for LazyDFA {
+    fn from(nfa: NFA) -> Self {
+        Self::with_capacity(nfa, Self::DEFAULT_CAPACITY)
+    }
+}
+
+impl LazyDFA {
+    pub const DEFAULT_CAPACITY: usize = 1 << 12;
+
+    pub fn with_capacity(nfa: NFA, capacity: usize) -> Self {
+        let closures = e_closure(&nfa.next);
+        let initial = nfa
+            .initials
+            .iter()
+            .flat_map(|&x| closures[x].iter().copied())
+            .collect();
+
+        Self {
+            nfa,
+            closures,
+            initial,
+            capacity: capacity.max(1),
+            states: TypedVec::new(),
+            translate: HashMap::new(),
+            evictions: 0,
+        }
+    }
+
+    pub fn cache_len(&self) -> usize {
+        self.states.len()
+    }
+
+    // The number of times the cache was cleared.
+    pub fn evictions(&self) -> usize {
+        self.evictions
+    }
+
+    // Returns the state for the subset, and whether the cache was cleared to make room for it, in
+    // which case every previously returned state is invalid.
+    fn intern(&mut self, subset: BTreeSet<State>) -> (State, bool) {
+        if let Some(&state) = self.translate.get(&subset) {
+            return (state, false);
+        }
+
+        let evicted = self.states.len() >= self.capacity;
+        if evicted {
+            self.states.clear();
+            self.translate.clear();
+            self.evictions += 1;
+        }
+
+        let state = State::from(self.states.len());
+        self.states.push(CachedState {
+            is_final: subset.iter().any(|x| self.nfa.finals.contains(x)),
+            subset: subset.clone(),
+            next: HashMap::new(),
+        });
+        self.translate.insert(subset, state);
+        (state, evicted)
+    }
+
+    fn next_state(&mut self, state: State, symbol: Symbol) -> Option<State> {
+        if let Some(&next_state) = self.states[state].next.get(&symbol) {
+            return next_state;
+        }
+
+        let subset: BTreeSet<State> = self.states[state]
+            .subset
+            .iter()
+            .flat_map(|&x| self.nfa.next[x].get(&symbol).into_iter().flatten())
+            .flat_map(|&x| self.closures[x].iter().copied())
+            .collect();
+
+        let next_state = if subset.is_empty() {
+            None
+        } else {
+            let (next_state, evicted) = self.intern(subset);
+            if evicted {
+                // `state` was evicted with the rest of the cache, so there is nothing to update.
+                return Some(next_state);
+            }
+            Some(next_state)
+        };
+
+        self.states[state].next.insert(symbol, next_state);
+        next_state
+    }
+
+    pub fn matches(&mut self, string: &[Symbol]) -> bool {
+        if self.initial.is_empty() {
+            return false;
+        }
+        let (mut state, _) = self.intern(self.initial.clone());
+
+        for &symbol in string {
+            match self.next_state(state, symbol) {
+                Some(next_state) => state = next_state,
+                None => return false,
+            }
+        }
+
+        self.states[state].is_final
+    }
+}
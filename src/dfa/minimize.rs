@@ -5,7 +5,7 @@ use std::iter::FromIterator;
 use crate::{dfa, State, Symbol};
 
 pub fn find_same(dfa: &dfa::NextElems, finals: &HashSet<State>) -> Vec<Vec<State>> {
-    let statenum = dfa.len().into();
+    let statenum = dfa.len();
     let mut different: HashSet<(State, State)> = HashSet::new();
 
     for i in (0..statenum).map(|x| x.into()) {
@@ -29,7 +29,7 @@ pub fn find_same(dfa: &dfa::NextElems, finals: &HashSet<State>) -> Vec<Vec<State
                 let symbols1: HashSet<Symbol> = HashSet::from_iter(dfa[i].keys().copied());
                 let symbols2: HashSet<Symbol> = HashSet::from_iter(dfa[j].keys().copied());
 
-                if symbols1.symmetric_difference(&symbols2).next() != None {
+                if symbols1.symmetric_difference(&symbols2).next().is_some() {
                     different.insert((i, j));
                     different.insert((j, i));
                     fixpoint = false;
@@ -37,8 +37,8 @@ pub fn find_same(dfa: &dfa::NextElems, finals: &HashSet<State>) -> Vec<Vec<State
                 }
 
                 for symbol in dfa[i].keys() {
-                    let next1 = dfa[i][&symbol];
-                    let next2 = dfa[j][&symbol];
+                    let next1 = dfa[i][symbol];
+                    let next2 = dfa[j][symbol];
 
                     if different.contains(&(next1, next2)) {
                         different.insert((i, j));
@@ -51,7 +51,7 @@ pub fn find_same(dfa: &dfa::NextElems, finals: &HashSet<State>) -> Vec<Vec<State
         }
     }
 
-    let mut ret = vec![Vec::new(); statenum as usize];
+    let mut ret = vec![Vec::new(); statenum];
     for i in (0..statenum).map(|x| x.into()) {
         for j in ((usize::from(i) + 1)..statenum).map(|x| x.into()) {
             if !different.contains(&(i, j)) {
@@ -73,11 +73,7 @@ pub fn minimize_dfa(dfa: &mut dfa::NextElems, finals: &mut HashSet<State>) {
     let mut curr_state_id = 0;
 
     for (state_id, state) in dfa.iter() {
-        if *same[usize::from(state_id)]
-            .get(0)
-            .unwrap_or_else(|| &state_id)
-            < state_id
-        {
+        if *same[usize::from(state_id)].first().unwrap_or(&state_id) < state_id {
             continue;
         }
 
@@ -86,7 +82,7 @@ pub fn minimize_dfa(dfa: &mut dfa::NextElems, finals: &mut HashSet<State>) {
 
         let mut new_state = dfa::NextElem::new();
         for (symbol, next) in state.iter() {
-            let untranslated_actual_next = match same[usize::from(*next)].get(0) {
+            let untranslated_actual_next = match same[usize::from(*next)].first() {
                 Some(x) => min(x, next),
                 None => next,
             };
@@ -97,7 +93,7 @@ pub fn minimize_dfa(dfa: &mut dfa::NextElems, finals: &mut HashSet<State>) {
 
     for (_, state) in new_dfa.iter_mut() {
         for (_, next) in state.iter_mut() {
-            *next = *translate.get(next).unwrap_or_else(|| next);
+            *next = *translate.get(next).unwrap_or(next);
         }
     }
 
@@ -1,13 +1,39 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    io, iter,
+};
 
-use crate::{nfa::NFA, State, Symbol};
+use crate::{
+    att, check_state, check_state_count, dot, nfa::NFA, DotOptions, LimitError, Limits, State,
+    Symbol, SymbolTable,
+};
 
-use nicole::typedvec::TypedVec;
+use nicole::{typedvec::TypedVec, IdLike};
 
+mod binary;
+mod classes;
 mod convert;
+mod cursor;
+mod dense;
 mod generate;
+mod lazy;
 mod minimize;
+mod multi;
 mod reverse;
+mod search;
+
+pub use self::{
+    binary::{BinaryLayout, DfaRef},
+    classes::SymbolClasses,
+    cursor::{Cursor, LiveDFA, StepResult},
+    dense::DenseDFA,
+    lazy::LazyDFA,
+    multi::MultiDFA,
+    search::{FindIter, Finder, MatchKind},
+};
+
+pub(crate) use self::cursor::live_states;
 
 pub type NextElem = HashMap<Symbol, State>;
 pub type NextElems = TypedVec<State, NextElem>;
@@ -21,61 +47,169 @@ pub struct DFA {
 
 impl From<NFA> for DFA {
     fn from(nfa: NFA) -> Self {
-        convert::nfa_to_dfa(nfa)
+        convert::nfa_to_dfa(&nfa)
     }
 }
 
 impl DFA {
+    pub fn try_from_nfa(nfa: &NFA, limits: Limits) -> Result<Self, LimitError> {
+        convert::try_nfa_to_dfa(nfa, limits)
+    }
+
     pub fn minimize(mut self) -> Self {
         minimize::minimize_dfa(&mut self.next, &mut self.finals);
         self
     }
 
-    pub fn print_graphviz(&self) {
-        indoc::printdoc!(
-            r#"
-            digraph finite_state_machine {{
-                rankdir=LR;
-                size="8,5"
-        "#
-        );
-
-        for x in self.finals.iter() {
-            println!("    node [shape = doublecircle]; {terminal};", terminal = x);
-        }
-        println!("    node [shape = circle];");
+    // Rewrites the transitions over symbol classes; input strings must be translated with the
+    // returned classes before being matched.
+    pub fn compress_alphabet(mut self) -> (Self, SymbolClasses) {
+        let classes = classes::symbol_classes(&self.next);
+        self.next = classes.compress(&self.next);
+        (self, classes)
+    }
 
-        for state_id in 0..self.next.len() {
-            println!(
-                "    q{state} [ label=<q<SUB>{state}</SUB>> ];",
-                state = state_id
-            );
-        }
+    // Builds a DFA from a transition list, checking that all states are in range and that the
+    // transitions are deterministic and epsilon-free.
+    pub(crate) fn from_transitions(
+        states: usize,
+        initial: State,
+        finals: impl IntoIterator<Item = State>,
+        transitions: impl IntoIterator<Item = (State, Symbol, State)>,
+    ) -> Result<Self, String> {
+        let states = check_state_count(states)?;
+        let mut next = NextElems::new();
+        next.try_reserve_exact(states)
+            .map_err(|_| format!("cannot allocate {} states", states))?;
+        next.resize_with(states, HashMap::new);
 
-        for (state, dict) in self.next.iter() {
-            for (symbol, next_state) in dict.iter() {
-                println!(
-                    "    {state} -> {next_state} [ label={symbol} ];",
-                    state = state,
-                    next_state = next_state,
-                    symbol = symbol
-                );
+        for (state, symbol, next_state) in transitions {
+            let state = check_state(state, states)?;
+            let next_state = check_state(next_state, states)?;
+            if symbol.is_null() {
+                return Err(format!("epsilon transition from {}", state));
+            }
+            match next[state].insert(symbol, next_state) {
+                Some(x) if x != next_state => {
+                    return Err(format!(
+                        "nondeterministic transition from {} on {}",
+                        state, symbol
+                    ));
+                }
+                _ => {}
             }
         }
-        println!("}}");
+
+        Ok(Self {
+            next,
+            initial: check_state(initial, states)?,
+            finals: finals
+                .into_iter()
+                .map(|x| check_state(x, states))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn from_dot(s: &str) -> Result<Self, Box<dyn Error>> {
+        let parsed = dot::parse_dot(s)?;
+        let initial = match parsed.initials.iter().collect::<Vec<_>>()[..] {
+            [&initial] => initial,
+            _ => return Err("a DFA must have exactly one initial state".into()),
+        };
+
+        Ok(Self::from_transitions(
+            parsed.state_count,
+            initial,
+            parsed.finals,
+            parsed.edges,
+        )?)
+    }
+
+    pub fn read_att(
+        r: impl io::BufRead,
+        symbols: Option<&SymbolTable>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let parsed = att::read_att(r, symbols)?;
+        let initial = parsed.start.ok_or("a DFA must have an initial state")?;
+        Ok(Self::from_transitions(
+            parsed.state_count,
+            initial,
+            parsed.finals,
+            parsed.edges,
+        )?)
+    }
+
+    pub fn write_att<W: io::Write>(&self, w: W, symbols: Option<&SymbolTable>) -> io::Result<()> {
+        att::write_att(
+            w,
+            self.initial,
+            &self.finals,
+            self.next.iter().flat_map(|(state, dict)| {
+                dict.iter()
+                    .map(move |(&symbol, &next_state)| (state, symbol, next_state))
+            }),
+            symbols,
+        )
+    }
+
+    pub fn to_bytes(&self, layout: BinaryLayout) -> Vec<u8> {
+        binary::to_bytes(self, layout)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        DfaRef::new(bytes)?.to_dfa()
+    }
+
+    pub fn write_dot<W: io::Write>(&self, w: W, options: &DotOptions) -> io::Result<()> {
+        dot::write_dot(
+            w,
+            self.next.len(),
+            iter::once(self.initial),
+            self.finals.iter().copied(),
+            self.next.iter().flat_map(|(state, dict)| {
+                dict.iter()
+                    .map(move |(&symbol, &next_state)| (state, symbol, next_state))
+            }),
+            options,
+        )
+    }
+
+    pub fn print_graphviz(&self) {
+        self.write_dot(io::stdout().lock(), &DotOptions::default())
+            .unwrap();
+    }
+
+    pub fn to_dense(&self) -> DenseDFA {
+        DenseDFA::from(self)
+    }
+
+    // Returns the state reached after reading the whole string, or `None` if the DFA died.
+    pub fn run(&self, string: &[Symbol]) -> Option<State> {
+        self.run_iter(string.iter().copied())
     }
 
     pub fn matches(&self, string: &[Symbol]) -> bool {
+        self.run(string)
+            .is_some_and(|state| self.finals.contains(&state))
+    }
+
+    pub fn matches_bytes(&self, bytes: &[u8]) -> bool {
+        self.run_iter(bytes.iter().copied().map(Symbol::from_byte))
+            .is_some_and(|state| self.finals.contains(&state))
+    }
+
+    pub fn matches_str(&self, s: &str) -> bool {
+        self.matches_bytes(s.as_bytes())
+    }
+
+    fn run_iter(&self, string: impl IntoIterator<Item = Symbol>) -> Option<State> {
         let mut state = self.initial;
 
         for symbol in string {
-            match self.next[state].get(symbol) {
-                Some(&next_state) => state = next_state,
-                None => return false,
-            }
+            state = *self.next[state].get(&symbol)?;
         }
 
-        self.finals.contains(&state)
+        Some(state)
     }
 
     pub fn reverse(&self) -> NFA {
@@ -0,0 +1,149 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    error::Error,
+};
+
+use nicole::typedvec::TypedVec;
+
+use crate::{refine::refine, LimitError, Limits, State, Symbol, NFA};
+
+use super::{convert::determinize, NextElems};
+
+static NO_PATTERNS: BTreeSet<usize> = BTreeSet::new();
+
+// A DFA for several patterns at once, where each final state is tagged with the ids of the
+// patterns it accepts. Pattern ids are the positions of the patterns in the input.
+#[derive(Clone, Debug)]
+pub struct MultiDFA {
+    pub next: NextElems,
+    pub initial: State,
+    pub tags: HashMap<State, BTreeSet<usize>>,
+}
+
+impl MultiDFA {
+    pub fn from_regexes<'a>(
+        regexes: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let nfas = regexes
+            .into_iter()
+            .map(|regex| regex.parse::<NFA>())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::from_nfas(&nfas))
+    }
+
+    pub fn from_nfas(nfas: &[NFA]) -> Self {
+        Self::try_from_nfas(nfas, Limits::UNLIMITED).unwrap()
+    }
+
+    // Determinizes the disjoint union of the NFAs; the tags of a DFA state are the patterns with
+    // a final state in its subset.
+    pub fn try_from_nfas(nfas: &[NFA], limits: Limits) -> Result<Self, LimitError> {
+        let mut union = NFA {
+            next: crate::nfa::NextElems::new(),
+            initials: Default::default(),
+            finals: Default::default(),
+        };
+        let mut patterns: TypedVec<State, Option<usize>> = TypedVec::new();
+
+        for (pattern, nfa) in nfas.iter().enumerate() {
+            let offset = union.next.len();
+            let shift = |state: State| State::from(usize::from(state) + offset);
+
+            union.next.extend(nfa.next.iter().map(|(_, dict)| {
+                dict.iter()
+                    .map(|(&symbol, next_states)| {
+                        (symbol, next_states.iter().copied().map(shift).collect())
+                    })
+                    .collect()
+            }));
+            union
+                .initials
+                .extend(nfa.initials.iter().copied().map(shift));
+            union.finals.extend(nfa.finals.iter().copied().map(shift));
+            patterns.extend(
+                nfa.next
+                    .iter()
+                    .map(|(state, _)| nfa.finals.contains(&state).then_some(pattern)),
+            );
+        }
+
+        let mut tags = HashMap::new();
+        let dfa = determinize(&union, limits, |state, subset| {
+            let state_tags: BTreeSet<usize> = subset.iter().filter_map(|&x| patterns[x]).collect();
+            if !state_tags.is_empty() {
+                tags.insert(state, state_tags);
+            }
+        })?;
+
+        Ok(Self {
+            next: dfa.next,
+            initial: dfa.initial,
+            tags,
+        })
+    }
+
+    // The ids of the patterns matching the whole string.
+    pub fn patterns(&self, string: &[Symbol]) -> &BTreeSet<usize> {
+        let mut state = self.initial;
+
+        for symbol in string {
+            match self.next[state].get(symbol) {
+                Some(&next_state) => state = next_state,
+                None => return &NO_PATTERNS,
+            }
+        }
+
+        self.tags.get(&state).unwrap_or(&NO_PATTERNS)
+    }
+
+    pub fn matches(&self, string: &[Symbol]) -> bool {
+        !self.patterns(string).is_empty()
+    }
+
+    fn signature(
+        &self,
+        blocks: &TypedVec<State, usize>,
+        state: State,
+    ) -> (BTreeSet<usize>, BTreeMap<Symbol, usize>) {
+        (
+            self.tags.get(&state).cloned().unwrap_or_default(),
+            self.next[state]
+                .iter()
+                .map(|(&symbol, &next_state)| (symbol, blocks[next_state]))
+                .collect(),
+        )
+    }
+
+    // Tags are part of the signature, so only states accepting exactly the same patterns are
+    // merged.
+    pub fn minimize(self) -> Self {
+        let blocks = refine(self.next.len(), |blocks, state| {
+            self.signature(blocks, state)
+        });
+
+        let mut next = NextElems::new();
+        let mut tags = HashMap::new();
+        for (state, _) in self.next.iter() {
+            if blocks[state] < next.len() {
+                continue;
+            }
+
+            let (state_tags, by_symbol) = self.signature(&blocks, state);
+            if !state_tags.is_empty() {
+                tags.insert(State::from(blocks[state]), state_tags);
+            }
+            next.push(
+                by_symbol
+                    .into_iter()
+                    .map(|(symbol, block)| (symbol, block.into()))
+                    .collect(),
+            );
+        }
+
+        Self {
+            next,
+            initial: blocks[self.initial].into(),
+            tags,
+        }
+    }
+}
@@ -4,7 +4,7 @@ use crate::{nfa, DFA, NFA};
 
 pub fn reverse(dfa: &DFA) -> NFA {
     let mut prev: nfa::NextElems = nfa::NextElems::new();
-    prev.resize_with(dfa.next.len(), || HashMap::new());
+    prev.resize_with(dfa.next.len(), HashMap::new);
 
     for (state, dict) in dfa.next.iter() {
         for (&symbol, &nextstate) in dict.iter() {
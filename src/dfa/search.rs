@@ -0,0 +1,292 @@
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+    ops::Range,
+};
+
+use nicole::{typedvec::TypedVec, IdLike};
+
+use crate::{nfa, State, Symbol, DFA, NFA};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MatchKind {
+    // The match starting leftmost, extended as far as possible.
+    #[default]
+    LeftmostLongest,
+    // The match starting leftmost that a backtracking regex engine reports, i.e. preferring earlier
+    // alternatives and greedy repetition. A DFA keeps no such priorities, see `Finder::from_nfa`.
+    LeftmostFirst,
+    // The match starting leftmost, ending at the first accepting state.
+    LeftmostShortest,
+}
+
+// The automaton that picks the ends of matches once their start is known.
+#[derive(Clone, Debug)]
+enum Forward<'a> {
+    Dfa(&'a DFA),
+    Nfa(&'a NFA),
+}
+
+// Finds unanchored matches. A backward pass over the haystack with the reversed automaton, behind
+// a `Σ*` loop, marks every position where a match starts; an anchored forward run from a start
+// then picks the end according to the match kind.
+#[derive(Clone, Debug)]
+pub struct Finder<'a> {
+    forward: Forward<'a>,
+    reverse: DFA,
+    kind: MatchKind,
+}
+
+fn reverse_nfa(nfa: &NFA) -> NFA {
+    let mut prev: nfa::NextElems = nfa::NextElems::new();
+    prev.resize_with(nfa.next.len(), HashMap::new);
+
+    for (state, dict) in nfa.next.iter() {
+        for (&symbol, next_states) in dict.iter() {
+            for &next_state in next_states {
+                prev[next_state].entry(symbol).or_default().insert(state);
+            }
+        }
+    }
+
+    NFA {
+        next: prev,
+        initials: nfa.finals.clone(),
+        finals: nfa.initials.clone(),
+    }
+}
+
+// Determinizes the reversed automaton behind a new initial state, which loops on every symbol of
+// the alphabet and moves to the old initial states; symbols outside the alphabet are handled in
+// `starts`. This can take exponential time and space in the number of states.
+fn starts_dfa(mut reverse: NFA) -> DFA {
+    let alphabet: HashSet<Symbol> = reverse
+        .next
+        .iter()
+        .flat_map(|(_, dict)| dict.keys().copied())
+        .filter(|symbol| !symbol.is_null())
+        .collect();
+
+    let start = State::from(reverse.next.len());
+    reverse.next.push(
+        alphabet
+            .iter()
+            .map(|&symbol| (symbol, [start].into()))
+            .chain([(Symbol::null(), reverse.initials.iter().copied().collect())])
+            .collect(),
+    );
+    reverse.initials = [start].into();
+
+    DFA::from(reverse).minimize()
+}
+
+// Adds `state` and its epsilon closure to `states` in priority order, skipping states already
+// added at `position`.
+fn add_state(
+    nfa: &NFA,
+    states: &mut Vec<State>,
+    added: &mut TypedVec<State, usize>,
+    position: usize,
+    state: State,
+) {
+    let mut stack = vec![state];
+    while let Some(state) = stack.pop() {
+        if added[state] == position {
+            continue;
+        }
+        added[state] = position;
+        states.push(state);
+        if let Some(next_states) = nfa.next[state].get(&Symbol::null()) {
+            stack.extend(next_states.iter().rev());
+        }
+    }
+}
+
+// Runs the automaton from `start`, reporting every position where it accepts, in increasing
+// order; with a match kind, the last position reported is the end of the match. The NFA is
+// simulated with its states in priority order, as the threads of a Pike VM, so that leftmost-first
+// can discard every state behind an accepting one.
+fn run(
+    forward: &Forward,
+    haystack: &[Symbol],
+    start: usize,
+    kind: Option<MatchKind>,
+    mut report: impl FnMut(usize),
+) {
+    match *forward {
+        Forward::Dfa(dfa) => {
+            let mut state = dfa.initial;
+            for i in start..=haystack.len() {
+                if dfa.finals.contains(&state) {
+                    report(i);
+                    if kind == Some(MatchKind::LeftmostShortest) {
+                        return;
+                    }
+                }
+                match haystack
+                    .get(i)
+                    .and_then(|symbol| dfa.next[state].get(symbol))
+                {
+                    Some(&next_state) => state = next_state,
+                    None => return,
+                }
+            }
+        }
+        Forward::Nfa(nfa) => {
+            let mut added: TypedVec<State, usize> = TypedVec::new();
+            added.resize(nfa.next.len(), usize::MAX);
+            let mut initials: Vec<State> = nfa.initials.iter().copied().collect();
+            initials.sort();
+
+            let mut states = Vec::new();
+            for state in initials {
+                add_state(nfa, &mut states, &mut added, start, state);
+            }
+
+            let mut next = Vec::new();
+            for i in start..=haystack.len() {
+                let mut accepted = false;
+                for &state in states.iter() {
+                    if nfa.finals.contains(&state) {
+                        if !accepted {
+                            report(i);
+                            accepted = true;
+                        }
+                        match kind {
+                            Some(MatchKind::LeftmostShortest) => return,
+                            Some(MatchKind::LeftmostFirst) => break,
+                            _ => {}
+                        }
+                    }
+                    let next_states = haystack
+                        .get(i)
+                        .and_then(|symbol| nfa.next[state].get(symbol));
+                    for &next_state in next_states.into_iter().flatten() {
+                        add_state(nfa, &mut next, &mut added, i + 1, next_state);
+                    }
+                }
+
+                if next.is_empty() {
+                    return;
+                }
+                mem::swap(&mut states, &mut next);
+                next.clear();
+            }
+        }
+    }
+}
+
+impl<'a> Finder<'a> {
+    // Determinizes the reversed DFA, which can take exponential time and space in the number of
+    // states; searching afterwards is linear in the haystack per match. Panics on
+    // `MatchKind::LeftmostFirst`, which needs `from_nfa`.
+    pub fn new(dfa: &'a DFA, kind: MatchKind) -> Self {
+        assert_ne!(
+            kind,
+            MatchKind::LeftmostFirst,
+            "leftmost-first search needs an NFA"
+        );
+
+        Self {
+            forward: Forward::Dfa(dfa),
+            reverse: starts_dfa(dfa.reverse()),
+            kind,
+        }
+    }
+
+    // Like `new`, but the ends of matches are picked by simulating the NFA, which is slower than
+    // running a DFA. Among the epsilon transitions of a state, those to smaller states have
+    // priority, as they do in NFAs compiled from regexes; every match kind is supported.
+    pub fn from_nfa(nfa: &'a NFA, kind: MatchKind) -> Self {
+        Self {
+            forward: Forward::Nfa(nfa),
+            reverse: starts_dfa(reverse_nfa(nfa)),
+            kind,
+        }
+    }
+
+    // `ret[i]` is true if a match starts at `i`.
+    fn starts(&self, haystack: &[Symbol]) -> Vec<bool> {
+        let mut ret = vec![false; haystack.len() + 1];
+        let mut state = self.reverse.initial;
+        ret[haystack.len()] = self.reverse.finals.contains(&state);
+
+        for (i, symbol) in haystack.iter().enumerate().rev() {
+            // A symbol outside the alphabet kills every partial match, leaving only the `Σ*` loop.
+            state = self.reverse.next[state]
+                .get(symbol)
+                .copied()
+                .unwrap_or(self.reverse.initial);
+            ret[i] = self.reverse.finals.contains(&state);
+        }
+
+        ret
+    }
+
+    pub fn find(&self, haystack: &[Symbol]) -> Option<Range<usize>> {
+        self.find_iter(haystack).next()
+    }
+
+    pub fn find_iter<'h>(&self, haystack: &'h [Symbol]) -> FindIter<'_, 'h> {
+        FindIter {
+            finder: self,
+            haystack,
+            starts: self.starts(haystack),
+            position: 0,
+            overlapping: false,
+            start: 0,
+            ends: Vec::new(),
+        }
+    }
+
+    // Reports every match, i.e. every pair of start and end, ordered by start and then by end.
+    // The match kind does not apply.
+    pub fn find_overlapping<'h>(&self, haystack: &'h [Symbol]) -> FindIter<'_, 'h> {
+        FindIter {
+            overlapping: true,
+            ..self.find_iter(haystack)
+        }
+    }
+}
+
+pub struct FindIter<'f, 'h> {
+    finder: &'f Finder<'f>,
+    haystack: &'h [Symbol],
+    starts: Vec<bool>,
+    position: usize,
+    overlapping: bool,
+    // The remaining ends of overlapping matches from `start`, last first.
+    start: usize,
+    ends: Vec<usize>,
+}
+
+impl<'f, 'h> Iterator for FindIter<'f, 'h> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(end) = self.ends.pop() {
+            return Some(self.start..end);
+        }
+
+        let start = (self.position..self.starts.len()).find(|&i| self.starts[i])?;
+        let forward = &self.finder.forward;
+        if self.overlapping {
+            run(forward, self.haystack, start, None, |end| {
+                self.ends.push(end)
+            });
+            self.ends.reverse();
+            self.start = start;
+            self.position = start + 1;
+            return self.ends.pop().map(|end| start..end);
+        }
+
+        let mut end = start;
+        run(forward, self.haystack, start, Some(self.finder.kind), |x| {
+            end = x
+        });
+
+        // After an empty match, the search resumes one position later.
+        self.position = if end == start { start + 1 } else { end };
+        Some(start..end)
+    }
+}
@@ -0,0 +1,174 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    error::Error,
+    fmt::{self, Display},
+    io::{self, Write},
+};
+
+use lalrpop_util::lalrpop_mod;
+
+use crate::State;
+
+lalrpop_mod!(
+    #[allow(clippy::all)]
+    parser,
+    "/src/dot/parser.rs"
+);
+mod parse;
+
+pub(crate) use self::parse::ParsedDot;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RankDir {
+    #[default]
+    LR,
+    RL,
+    TB,
+    BT,
+}
+
+impl Display for RankDir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            RankDir::LR => "LR",
+            RankDir::RL => "RL",
+            RankDir::TB => "TB",
+            RankDir::BT => "BT",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DotOptions {
+    pub rankdir: RankDir,
+    pub size: Option<(f64, f64)>,
+    pub show_initial: bool,
+    // Draw a single edge labelled e.g. "a,b" instead of one edge per symbol.
+    pub merge_edges: bool,
+    pub state_labels: HashMap<State, String>,
+    pub highlight_states: HashSet<State>,
+    pub highlight_edges: HashSet<(State, State)>,
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        Self {
+            rankdir: RankDir::LR,
+            size: Some((8.0, 5.0)),
+            show_initial: true,
+            merge_edges: false,
+            state_labels: HashMap::new(),
+            highlight_states: HashSet::new(),
+            highlight_edges: HashSet::new(),
+        }
+    }
+}
+
+impl DotOptions {
+    pub fn highlight_path(mut self, path: &[State]) -> Self {
+        self.highlight_states.extend(path.iter().copied());
+        self.highlight_edges
+            .extend(path.windows(2).map(|pair| (pair[0], pair[1])));
+        self
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Edges are usually labelled with symbols, but any label can be used, e.g. "in/out" for
+// transducers.
+pub(crate) fn write_dot<W: Write, L: Ord + Display>(
+    mut w: W,
+    state_count: usize,
+    initials: impl IntoIterator<Item = State>,
+    finals: impl IntoIterator<Item = State>,
+    edges: impl IntoIterator<Item = (State, L, State)>,
+    options: &DotOptions,
+) -> io::Result<()> {
+    let mut initials: Vec<State> = initials.into_iter().collect();
+    initials.sort();
+    let mut finals: Vec<State> = finals.into_iter().collect();
+    finals.sort();
+
+    let mut grouped: BTreeMap<(State, State), Vec<L>> = BTreeMap::new();
+    for (state, symbol, next_state) in edges {
+        grouped.entry((state, next_state)).or_default().push(symbol);
+    }
+
+    writeln!(w, "digraph finite_state_machine {{")?;
+    writeln!(w, "    rankdir={};", options.rankdir)?;
+    if let Some((width, height)) = options.size {
+        writeln!(w, "    size=\"{},{}\"", width, height)?;
+    }
+    for x in finals.iter() {
+        writeln!(
+            w,
+            "    node [shape = doublecircle]; {terminal};",
+            terminal = x
+        )?;
+    }
+    writeln!(w, "    node [shape = circle];")?;
+    writeln!(w)?;
+
+    if options.show_initial {
+        writeln!(w, "    __start [ shape = none, label = \"\" ];")?;
+    }
+
+    for state in (0..state_count).map(State::from) {
+        let label = match options.state_labels.get(&state) {
+            Some(label) => format!("\"{}\"", escape(label)),
+            None => format!("<q<SUB>{}</SUB>>", usize::from(state)),
+        };
+        let highlight = if options.highlight_states.contains(&state) {
+            ", color = red, penwidth = 2"
+        } else {
+            ""
+        };
+        writeln!(w, "    {state} [ label={label}{highlight} ];")?;
+    }
+
+    if options.show_initial {
+        for x in initials.iter() {
+            writeln!(w, "    __start -> {initial};", initial = x)?;
+        }
+    }
+
+    for ((state, next_state), mut symbols) in grouped {
+        symbols.sort();
+        let highlight = if options.highlight_edges.contains(&(state, next_state)) {
+            ", color = red, penwidth = 2"
+        } else {
+            ""
+        };
+
+        let labels: Vec<String> = if options.merge_edges {
+            vec![symbols
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(",")]
+        } else {
+            symbols.iter().map(|x| x.to_string()).collect()
+        };
+
+        for label in labels {
+            writeln!(
+                w,
+                "    {state} -> {next_state} [ label=\"{label}\"{highlight} ];",
+                label = escape(&label)
+            )?;
+        }
+    }
+
+    writeln!(w, "}}")
+}
+
+pub(crate) fn parse_dot(s: &str) -> Result<ParsedDot, Box<dyn Error>> {
+    let stmts = parser::GraphParser::new()
+        .parse(s)
+        .map_err(|x| x.to_string())?;
+    parse::build(stmts)
+}
@@ -0,0 +1,122 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+};
+
+use crate::{state_from_id, State, Symbol};
+
+#[derive(Debug)]
+pub enum Stmt {
+    Ignored,
+    NodeDefaults(Vec<(String, String)>),
+    EdgeDefaults(Vec<(String, String)>),
+    Node(String, Vec<(String, String)>),
+    Edge(String, String, Vec<(String, String)>),
+}
+
+#[derive(Debug, Default)]
+pub struct ParsedDot {
+    pub state_count: usize,
+    pub initials: HashSet<State>,
+    pub finals: HashSet<State>,
+    pub edges: Vec<(State, Symbol, State)>,
+}
+
+// Returns `Ok(None)` for nodes that are not states.
+fn state_id(id: &str) -> Result<Option<State>, String> {
+    match id.strip_prefix('q').map(str::parse::<usize>) {
+        Some(Ok(x)) => state_from_id(x).map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn attr<'a>(attrs: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    attrs
+        .iter()
+        .rev()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
+
+// States are nodes named `qN`, numbered from q0 without gaps. A node is final if it is first mentioned while the default node
+// shape is `doublecircle`, or is given that shape explicitly. Edges from any other node (such as an invisible
+// `__start` node) mark their targets as initial; if there are none, q0 is the initial state.
+pub fn build(stmts: Vec<Stmt>) -> Result<ParsedDot, Box<dyn Error>> {
+    let mut ret = ParsedDot::default();
+    let mut shapes: HashMap<State, String> = HashMap::new();
+    let mut default_shape = String::from("ellipse");
+    let mut default_label: Option<String> = None;
+
+    // As in Graphviz, default attributes only apply to a node when it is first created.
+    let declare = |ret: &mut ParsedDot, shapes: &mut HashMap<State, String>, state, shape: &str| {
+        ret.state_count = ret.state_count.max(usize::from(state) + 1);
+        shapes.entry(state).or_insert_with(|| shape.to_string());
+    };
+
+    for stmt in stmts {
+        match stmt {
+            Stmt::Ignored => {}
+            Stmt::NodeDefaults(attrs) => {
+                if let Some(shape) = attr(&attrs, "shape") {
+                    default_shape = shape.to_string();
+                }
+            }
+            Stmt::EdgeDefaults(attrs) => {
+                if let Some(label) = attr(&attrs, "label") {
+                    default_label = Some(label.to_string());
+                }
+            }
+            Stmt::Node(id, attrs) => {
+                if let Some(state) = state_id(&id)? {
+                    declare(&mut ret, &mut shapes, state, &default_shape);
+                    if let Some(shape) = attr(&attrs, "shape") {
+                        shapes.insert(state, shape.to_string());
+                    }
+                }
+            }
+            Stmt::Edge(from, to, attrs) => {
+                let to_state = state_id(&to)?.ok_or_else(|| format!("invalid state id {}", to))?;
+                declare(&mut ret, &mut shapes, to_state, &default_shape);
+
+                let Some(from_state) = state_id(&from)? else {
+                    ret.initials.insert(to_state);
+                    continue;
+                };
+                declare(&mut ret, &mut shapes, from_state, &default_shape);
+
+                let label = attr(&attrs, "label")
+                    .map(str::to_string)
+                    .or_else(|| default_label.clone())
+                    .ok_or_else(|| format!("edge {} -> {} has no label", from, to))?;
+                for symbol in label.split(',') {
+                    let symbol: Symbol = symbol
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("invalid symbol {:?}", symbol))?;
+                    ret.edges.push((from_state, symbol, to_state));
+                }
+            }
+        }
+    }
+
+    // Sizing the automaton by the largest id alone would let a tiny input allocate billions of
+    // states.
+    if ret.state_count > shapes.len() {
+        let missing = (0..ret.state_count)
+            .map(State::from)
+            .find(|state| !shapes.contains_key(state))
+            .unwrap();
+        return Err(format!("state q{} is never declared", missing).into());
+    }
+
+    ret.finals = shapes
+        .into_iter()
+        .filter(|(_, shape)| shape == "doublecircle")
+        .map(|(state, _)| state)
+        .collect();
+    if ret.initials.is_empty() && ret.state_count > 0 {
+        ret.initials.insert(State(0));
+    }
+
+    Ok(ret)
+}
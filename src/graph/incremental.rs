@@ -0,0 +1,200 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use crate::{State, Symbol, DFA};
+
+#[derive(Clone, Debug)]
+struct SourceIndex<V: Eq + Hash> {
+    visited: HashMap<V, HashSet<State>>,
+    targets: HashSet<V>,
+}
+
+impl<V: Copy + Eq + Hash> SourceIndex<V> {
+    fn new() -> Self {
+        Self {
+            visited: HashMap::new(),
+            targets: HashSet::new(),
+        }
+    }
+
+    fn contains(&self, vertex: V, state: State) -> bool {
+        self.visited
+            .get(&vertex)
+            .is_some_and(|states| states.contains(&state))
+    }
+
+    // Extends the product search from (vertex, state), returning the targets that became reachable.
+    fn explore(
+        &mut self,
+        graph: &HashMap<V, Vec<(Symbol, V)>>,
+        dfa: &DFA,
+        vertex: V,
+        state: State,
+    ) -> Vec<V> {
+        let mut ret = Vec::new();
+        if !self.visited.entry(vertex).or_default().insert(state) {
+            return ret;
+        }
+
+        let mut stack = vec![(vertex, state)];
+        while let Some((vertex, state)) = stack.pop() {
+            if dfa.finals.contains(&state) && self.targets.insert(vertex) {
+                ret.push(vertex);
+            }
+
+            for &(symbol, next_vertex) in graph.get(&vertex).into_iter().flatten() {
+                if let Some(&next_state) = dfa.next[state].get(&symbol) {
+                    if self
+                        .visited
+                        .entry(next_vertex)
+                        .or_default()
+                        .insert(next_state)
+                    {
+                        stack.push((next_vertex, next_state));
+                    }
+                }
+            }
+        }
+
+        ret
+    }
+}
+
+// Regular reachability index for a fixed DFA and set of sources over a graph that changes over
+// time. Insertions only extend the product search from the endpoints of the new edge. Deletions
+// recompute the search, but only for the sources whose product search used the deleted edge.
+#[derive(Clone, Debug)]
+pub struct IncrementalRpq<V: Eq + Hash> {
+    dfa: DFA,
+    graph: HashMap<V, Vec<(Symbol, V)>>,
+    sources: HashMap<V, SourceIndex<V>>,
+}
+
+impl<V: Copy + Eq + Hash> IncrementalRpq<V> {
+    pub fn new(dfa: DFA, sources: impl IntoIterator<Item = V>) -> Self {
+        Self::from_edges(dfa, sources, [])
+    }
+
+    pub fn from_edges(
+        dfa: DFA,
+        sources: impl IntoIterator<Item = V>,
+        edges: impl IntoIterator<Item = (V, Symbol, V)>,
+    ) -> Self {
+        let mut ret = Self {
+            dfa,
+            graph: HashMap::new(),
+            sources: HashMap::new(),
+        };
+
+        for (from, symbol, to) in edges {
+            let out = ret.graph.entry(from).or_default();
+            if !out.contains(&(symbol, to)) {
+                out.push((symbol, to));
+            }
+        }
+        for source in sources {
+            ret.add_source(source);
+        }
+
+        ret
+    }
+
+    pub fn graph(&self) -> &HashMap<V, Vec<(Symbol, V)>> {
+        &self.graph
+    }
+
+    pub fn contains(&self, source: V, target: V) -> bool {
+        self.sources
+            .get(&source)
+            .is_some_and(|index| index.targets.contains(&target))
+    }
+
+    pub fn pairs(&self) -> HashSet<(V, V)> {
+        self.sources
+            .iter()
+            .flat_map(|(&source, index)| index.targets.iter().map(move |&target| (source, target)))
+            .collect()
+    }
+
+    // Returns the (source, target) pairs that became reachable by adding the source.
+    pub fn add_source(&mut self, source: V) -> Vec<(V, V)> {
+        if self.sources.contains_key(&source) {
+            return Vec::new();
+        }
+
+        let mut index = SourceIndex::new();
+        let targets = index.explore(&self.graph, &self.dfa, source, self.dfa.initial);
+        self.sources.insert(source, index);
+
+        targets.into_iter().map(|target| (source, target)).collect()
+    }
+
+    // Returns the (source, target) pairs that became reachable by inserting the edge.
+    pub fn insert_edge(&mut self, from: V, symbol: Symbol, to: V) -> Vec<(V, V)> {
+        let out = self.graph.entry(from).or_default();
+        if out.contains(&(symbol, to)) {
+            return Vec::new();
+        }
+        out.push((symbol, to));
+
+        let mut ret = Vec::new();
+        for (&source, index) in self.sources.iter_mut() {
+            let next_states: Vec<State> = index
+                .visited
+                .get(&from)
+                .into_iter()
+                .flatten()
+                .filter_map(|&state| self.dfa.next[state].get(&symbol).copied())
+                .collect();
+
+            for next_state in next_states {
+                let targets = index.explore(&self.graph, &self.dfa, to, next_state);
+                ret.extend(targets.into_iter().map(|target| (source, target)));
+            }
+        }
+
+        ret
+    }
+
+    // Returns the (source, target) pairs that are no longer reachable after removing the edge.
+    pub fn remove_edge(&mut self, from: V, symbol: Symbol, to: V) -> Vec<(V, V)> {
+        let Some(out) = self.graph.get_mut(&from) else {
+            return Vec::new();
+        };
+        let Some(position) = out.iter().position(|&x| x == (symbol, to)) else {
+            return Vec::new();
+        };
+        out.swap_remove(position);
+
+        let mut ret = Vec::new();
+        for (&source, index) in self.sources.iter_mut() {
+            let affected = index
+                .visited
+                .get(&from)
+                .into_iter()
+                .flatten()
+                .any(|&state| {
+                    self.dfa.next[state]
+                        .get(&symbol)
+                        .is_some_and(|&next_state| index.contains(to, next_state))
+                });
+            if !affected {
+                continue;
+            }
+
+            let mut new_index = SourceIndex::new();
+            new_index.explore(&self.graph, &self.dfa, source, self.dfa.initial);
+            ret.extend(
+                index
+                    .targets
+                    .difference(&new_index.targets)
+                    .map(|&target| (source, target)),
+            );
+            *index = new_index;
+        }
+
+        ret
+    }
+}
@@ -0,0 +1,102 @@
+use std::{collections::HashMap, hash::Hash, ops::Add};
+
+use crate::Symbol;
+
+mod incremental;
+mod multi_source;
+mod paths;
+mod product;
+mod rpq;
+mod shortest;
+
+#[cfg(feature = "rayon")]
+pub use self::multi_source::par_multi_source_rpq;
+pub use self::{
+    incremental::IncrementalRpq,
+    multi_source::{multi_source_rpq, Reachability},
+    paths::{rpq_paths, rpq_with_semantics, MatchingPath, PathSemantics, Paths},
+    product::ProductGraph,
+    rpq::rpq,
+    shortest::{k_shortest_paths, shortest_path, shortest_path_astar, ConstrainedPath},
+};
+
+pub trait LabeledGraph {
+    type Vertex: Copy + Eq + Hash;
+
+    fn successors(&self, vertex: Self::Vertex)
+        -> impl Iterator<Item = (Symbol, Self::Vertex)> + '_;
+}
+
+pub trait WeightedLabeledGraph: LabeledGraph {
+    type Weight: Copy + Ord + Add<Output = Self::Weight> + Default;
+
+    fn weighted_successors(
+        &self,
+        vertex: Self::Vertex,
+    ) -> impl Iterator<Item = (Symbol, Self::Vertex, Self::Weight)> + '_;
+}
+
+impl<V: Copy + Eq + Hash> LabeledGraph for HashMap<V, Vec<(Symbol, V)>> {
+    type Vertex = V;
+
+    fn successors(&self, vertex: V) -> impl Iterator<Item = (Symbol, V)> + '_ {
+        self.get(&vertex).into_iter().flatten().copied()
+    }
+}
+
+impl LabeledGraph for Vec<Vec<(Symbol, usize)>> {
+    type Vertex = usize;
+
+    fn successors(&self, vertex: usize) -> impl Iterator<Item = (Symbol, usize)> + '_ {
+        self.get(vertex).into_iter().flatten().copied()
+    }
+}
+
+impl<V, W> LabeledGraph for HashMap<V, Vec<(Symbol, V, W)>>
+where
+    V: Copy + Eq + Hash,
+    W: Copy,
+{
+    type Vertex = V;
+
+    fn successors(&self, vertex: V) -> impl Iterator<Item = (Symbol, V)> + '_ {
+        self.get(&vertex)
+            .into_iter()
+            .flatten()
+            .map(|&(symbol, next, _)| (symbol, next))
+    }
+}
+
+impl<V, W> WeightedLabeledGraph for HashMap<V, Vec<(Symbol, V, W)>>
+where
+    V: Copy + Eq + Hash,
+    W: Copy + Ord + Add<Output = W> + Default,
+{
+    type Weight = W;
+
+    fn weighted_successors(&self, vertex: V) -> impl Iterator<Item = (Symbol, V, W)> + '_ {
+        self.get(&vertex).into_iter().flatten().copied()
+    }
+}
+
+impl<W: Copy> LabeledGraph for Vec<Vec<(Symbol, usize, W)>> {
+    type Vertex = usize;
+
+    fn successors(&self, vertex: usize) -> impl Iterator<Item = (Symbol, usize)> + '_ {
+        self.get(vertex)
+            .into_iter()
+            .flatten()
+            .map(|&(symbol, next, _)| (symbol, next))
+    }
+}
+
+impl<W> WeightedLabeledGraph for Vec<Vec<(Symbol, usize, W)>>
+where
+    W: Copy + Ord + Add<Output = W> + Default,
+{
+    type Weight = W;
+
+    fn weighted_successors(&self, vertex: usize) -> impl Iterator<Item = (Symbol, usize, W)> + '_ {
+        self.get(vertex).into_iter().flatten().copied()
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use crate::{State, DFA};
+
+use super::LabeledGraph;
+
+// Reachability relation indexed by (source, target, final state): `target` is reachable from
+// `source` along a path that drives the DFA from its initial state to the final state `state`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reachability<V: Eq + Hash> {
+    relation: HashMap<V, HashSet<(V, State)>>,
+}
+
+impl<V: Copy + Eq + Hash> Reachability<V> {
+    pub fn contains(&self, source: V, target: V, state: State) -> bool {
+        self.relation
+            .get(&source)
+            .is_some_and(|x| x.contains(&(target, state)))
+    }
+
+    pub fn targets(&self, source: V) -> impl Iterator<Item = (V, State)> + '_ {
+        self.relation.get(&source).into_iter().flatten().copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (V, V, State)> + '_ {
+        self.relation.iter().flat_map(|(&source, targets)| {
+            targets
+                .iter()
+                .map(move |&(target, state)| (source, target, state))
+        })
+    }
+
+    pub fn pairs(&self) -> HashSet<(V, V)> {
+        self.iter()
+            .map(|(source, target, _)| (source, target))
+            .collect()
+    }
+}
+
+struct Product<V> {
+    nodes: Vec<(V, State)>,
+    next: Vec<Vec<usize>>,
+    roots: Vec<usize>,
+}
+
+fn explore<G: LabeledGraph>(graph: &G, dfa: &DFA, sources: &[G::Vertex]) -> Product<G::Vertex> {
+    let mut translate: HashMap<(G::Vertex, State), usize> = HashMap::new();
+    let mut product = Product {
+        nodes: Vec::new(),
+        next: Vec::new(),
+        roots: Vec::new(),
+    };
+
+    for &source in sources {
+        if let Some(&id) = translate.get(&(source, dfa.initial)) {
+            product.roots.push(id);
+            continue;
+        }
+        product.roots.push(product.nodes.len());
+        translate.insert((source, dfa.initial), product.nodes.len());
+        product.nodes.push((source, dfa.initial));
+        product.next.push(Vec::new());
+
+        let mut stack = vec![product.nodes.len() - 1];
+        while let Some(id) = stack.pop() {
+            let (vertex, state) = product.nodes[id];
+            for (symbol, next_vertex) in graph.successors(vertex) {
+                let Some(&next_state) = dfa.next[state].get(&symbol) else {
+                    continue;
+                };
+                let next_id = *translate
+                    .entry((next_vertex, next_state))
+                    .or_insert_with(|| {
+                        product.nodes.push((next_vertex, next_state));
+                        product.next.push(Vec::new());
+                        stack.push(product.nodes.len() - 1);
+                        product.nodes.len() - 1
+                    });
+                product.next[id].push(next_id);
+            }
+        }
+    }
+
+    product
+}
+
+// Iterative Tarjan; components are numbered in reverse topological order (sinks first).
+fn strongly_connected_components(next: &[Vec<usize>]) -> (Vec<usize>, usize) {
+    const UNVISITED: usize = usize::MAX;
+
+    let mut index = vec![UNVISITED; next.len()];
+    let mut lowlink = vec![0; next.len()];
+    let mut component = vec![UNVISITED; next.len()];
+    let mut stack: Vec<usize> = Vec::new();
+    let mut counter = 0;
+    let mut component_count = 0;
+
+    for root in 0..next.len() {
+        if index[root] != UNVISITED {
+            continue;
+        }
+
+        let mut call_stack: Vec<(usize, usize)> = vec![(root, 0)];
+        index[root] = counter;
+        lowlink[root] = counter;
+        counter += 1;
+        stack.push(root);
+
+        while let Some(&mut (node, ref mut edge)) = call_stack.last_mut() {
+            if let Some(&child) = next[node].get(*edge) {
+                *edge += 1;
+                if index[child] == UNVISITED {
+                    index[child] = counter;
+                    lowlink[child] = counter;
+                    counter += 1;
+                    stack.push(child);
+                    call_stack.push((child, 0));
+                } else if component[child] == UNVISITED {
+                    lowlink[node] = lowlink[node].min(index[child]);
+                }
+                continue;
+            }
+
+            call_stack.pop();
+            if let Some(&(parent, _)) = call_stack.last() {
+                lowlink[parent] = lowlink[parent].min(lowlink[node]);
+            }
+            if lowlink[node] == index[node] {
+                while let Some(x) = stack.pop() {
+                    component[x] = component_count;
+                    if x == node {
+                        break;
+                    }
+                }
+                component_count += 1;
+            }
+        }
+    }
+
+    (component, component_count)
+}
+
+// Evaluates the query for all sources at once. The product of the graph and the DFA is explored
+// once, and the reachable (target, final state) pairs of every strongly connected component of the
+// product are computed once and shared by all sources reaching it.
+pub fn multi_source_rpq<G: LabeledGraph>(
+    graph: &G,
+    dfa: &DFA,
+    sources: impl IntoIterator<Item = G::Vertex>,
+) -> Reachability<G::Vertex> {
+    let sources: Vec<G::Vertex> = sources.into_iter().collect();
+    let product = explore(graph, dfa, &sources);
+    let (component, component_count) = strongly_connected_components(&product.next);
+
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); component_count];
+    for (id, &c) in component.iter().enumerate() {
+        members[c].push(id);
+    }
+
+    let mut reach: Vec<HashSet<(G::Vertex, State)>> = Vec::with_capacity(component_count);
+    for (c, ids) in members.iter().enumerate() {
+        let mut acc: HashSet<(G::Vertex, State)> = HashSet::new();
+        for &id in ids {
+            let (vertex, state) = product.nodes[id];
+            if dfa.finals.contains(&state) {
+                acc.insert((vertex, state));
+            }
+            for &next_id in product.next[id].iter() {
+                if component[next_id] != c {
+                    acc.extend(reach[component[next_id]].iter().copied());
+                }
+            }
+        }
+        reach.push(acc);
+    }
+
+    let relation = sources
+        .into_iter()
+        .zip(product.roots)
+        .map(|(source, id)| (source, reach[component[id]].clone()))
+        .collect();
+
+    Reachability { relation }
+}
+
+#[cfg(feature = "rayon")]
+pub fn par_multi_source_rpq<G>(
+    graph: &G,
+    dfa: &DFA,
+    sources: impl IntoIterator<Item = G::Vertex>,
+) -> Reachability<G::Vertex>
+where
+    G: LabeledGraph + Sync,
+    G::Vertex: Send + Sync,
+{
+    use rayon::prelude::*;
+
+    let sources: Vec<G::Vertex> = sources.into_iter().collect();
+    let chunk_size = (sources.len() / rayon::current_num_threads()).max(1);
+
+    let relation = sources
+        .par_chunks(chunk_size)
+        .map(|chunk| multi_source_rpq(graph, dfa, chunk.iter().copied()).relation)
+        .reduce(HashMap::new, |mut acc, x| {
+            acc.extend(x);
+            acc
+        });
+
+    Reachability { relation }
+}
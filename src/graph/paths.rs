@@ -0,0 +1,184 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{State, Symbol, DFA};
+
+use super::{rpq, LabeledGraph};
+
+type Node<V> = (V, State);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PathSemantics {
+    // Any walk; vertices and edges may repeat.
+    Arbitrary,
+    // No vertex appears twice on the path.
+    Simple,
+    // No edge, identified by (from, symbol, to), appears twice on the path.
+    Trail,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MatchingPath<V> {
+    pub vertices: Vec<V>,
+    pub symbols: Vec<Symbol>,
+    pub states: Vec<State>,
+}
+
+// Product nodes reachable from (source, initial) that can still reach a final state; partial paths
+// leaving this set are never extended.
+fn live_nodes<G: LabeledGraph>(
+    graph: &G,
+    dfa: &DFA,
+    source: G::Vertex,
+) -> HashSet<Node<G::Vertex>> {
+    let mut prev: HashMap<Node<G::Vertex>, Vec<Node<G::Vertex>>> = HashMap::new();
+    let mut visited: HashSet<Node<G::Vertex>> = HashSet::new();
+    let mut stack = vec![(source, dfa.initial)];
+    visited.insert((source, dfa.initial));
+
+    while let Some((vertex, state)) = stack.pop() {
+        for (symbol, next_vertex) in graph.successors(vertex) {
+            if let Some(&next_state) = dfa.next[state].get(&symbol) {
+                prev.entry((next_vertex, next_state))
+                    .or_default()
+                    .push((vertex, state));
+                if visited.insert((next_vertex, next_state)) {
+                    stack.push((next_vertex, next_state));
+                }
+            }
+        }
+    }
+
+    let mut stack: Vec<Node<G::Vertex>> = visited
+        .iter()
+        .copied()
+        .filter(|(_, state)| dfa.finals.contains(state))
+        .collect();
+    let mut live: HashSet<Node<G::Vertex>> = stack.iter().copied().collect();
+
+    while let Some(node) = stack.pop() {
+        for &prev_node in prev.get(&node).into_iter().flatten() {
+            if live.insert(prev_node) {
+                stack.push(prev_node);
+            }
+        }
+    }
+
+    live
+}
+
+pub struct Paths<'a, G: LabeledGraph> {
+    graph: &'a G,
+    dfa: &'a DFA,
+    semantics: PathSemantics,
+    live: HashSet<Node<G::Vertex>>,
+    frontier: VecDeque<MatchingPath<G::Vertex>>,
+}
+
+impl<'a, G: LabeledGraph> Paths<'a, G> {
+    fn allows(
+        &self,
+        path: &MatchingPath<G::Vertex>,
+        symbol: Symbol,
+        next_vertex: G::Vertex,
+    ) -> bool {
+        match self.semantics {
+            PathSemantics::Arbitrary => true,
+            PathSemantics::Simple => !path.vertices.contains(&next_vertex),
+            PathSemantics::Trail => {
+                let vertex = *path.vertices.last().unwrap();
+                !(0..path.symbols.len()).any(|i| {
+                    path.vertices[i] == vertex
+                        && path.symbols[i] == symbol
+                        && path.vertices[i + 1] == next_vertex
+                })
+            }
+        }
+    }
+}
+
+impl<'a, G: LabeledGraph> Iterator for Paths<'a, G> {
+    type Item = MatchingPath<G::Vertex>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Arbitrary walks are infinite in number, so they are enumerated breadth-first (shortest
+            // first); simple paths and trails are finite and enumerated depth-first.
+            let path = match self.semantics {
+                PathSemantics::Arbitrary => self.frontier.pop_front()?,
+                PathSemantics::Simple | PathSemantics::Trail => self.frontier.pop_back()?,
+            };
+            let vertex = *path.vertices.last().unwrap();
+            let state = *path.states.last().unwrap();
+
+            for (symbol, next_vertex) in self.graph.successors(vertex) {
+                let Some(&next_state) = self.dfa.next[state].get(&symbol) else {
+                    continue;
+                };
+                if !self.live.contains(&(next_vertex, next_state))
+                    || !self.allows(&path, symbol, next_vertex)
+                {
+                    continue;
+                }
+
+                let mut next_path = path.clone();
+                next_path.vertices.push(next_vertex);
+                next_path.symbols.push(symbol);
+                next_path.states.push(next_state);
+                self.frontier.push_back(next_path);
+            }
+
+            if self.dfa.finals.contains(&state) {
+                return Some(path);
+            }
+        }
+    }
+}
+
+// Lazily enumerates the paths starting at `source` whose labels are accepted by the DFA. Partial
+// paths that cannot reach a final state in the product are pruned, so under arbitrary semantics
+// every step leads towards an output. Under simple and trail semantics a partial path may still
+// die because of the repetition constraint, which is unavoidable in general.
+pub fn rpq_paths<'a, G: LabeledGraph>(
+    graph: &'a G,
+    dfa: &'a DFA,
+    source: G::Vertex,
+    semantics: PathSemantics,
+) -> Paths<'a, G> {
+    let live = live_nodes(graph, dfa, source);
+    let mut frontier = VecDeque::new();
+
+    if live.contains(&(source, dfa.initial)) {
+        frontier.push_back(MatchingPath {
+            vertices: vec![source],
+            symbols: vec![],
+            states: vec![dfa.initial],
+        });
+    }
+
+    Paths {
+        graph,
+        dfa,
+        semantics,
+        live,
+        frontier,
+    }
+}
+
+pub fn rpq_with_semantics<G: LabeledGraph>(
+    graph: &G,
+    dfa: &DFA,
+    sources: impl IntoIterator<Item = G::Vertex>,
+    semantics: PathSemantics,
+) -> HashSet<(G::Vertex, G::Vertex)> {
+    if semantics == PathSemantics::Arbitrary {
+        return rpq(graph, dfa, sources);
+    }
+
+    sources
+        .into_iter()
+        .flat_map(|source| {
+            rpq_paths(graph, dfa, source, semantics)
+                .map(move |path| (source, *path.vertices.last().unwrap()))
+        })
+        .collect()
+}
@@ -0,0 +1,32 @@
+use crate::{State, Symbol, DFA};
+
+use super::LabeledGraph;
+
+// The product of a labelled graph and a DFA, itself a labelled graph over (vertex, state) pairs.
+// An edge exists whenever the graph has an edge whose label the DFA can read from the state.
+pub struct ProductGraph<'a, G> {
+    graph: &'a G,
+    dfa: &'a DFA,
+}
+
+impl<'a, G: LabeledGraph> ProductGraph<'a, G> {
+    pub fn new(graph: &'a G, dfa: &'a DFA) -> Self {
+        Self { graph, dfa }
+    }
+}
+
+impl<'a, G: LabeledGraph> LabeledGraph for ProductGraph<'a, G> {
+    type Vertex = (G::Vertex, State);
+
+    fn successors(
+        &self,
+        (vertex, state): Self::Vertex,
+    ) -> impl Iterator<Item = (Symbol, Self::Vertex)> + '_ {
+        self.graph
+            .successors(vertex)
+            .filter_map(move |(symbol, next_vertex)| {
+                let &next_state = self.dfa.next[state].get(&symbol)?;
+                Some((symbol, (next_vertex, next_state)))
+            })
+    }
+}
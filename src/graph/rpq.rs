@@ -0,0 +1,39 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::{State, DFA};
+
+use super::LabeledGraph;
+
+// Returns every (source, target) pair such that some path from source to target spells a word
+// accepted by the DFA, by a BFS over the product of the graph and the automaton.
+pub fn rpq<G: LabeledGraph>(
+    graph: &G,
+    dfa: &DFA,
+    sources: impl IntoIterator<Item = G::Vertex>,
+) -> HashSet<(G::Vertex, G::Vertex)> {
+    let mut ret = HashSet::new();
+
+    for source in sources {
+        let mut visited: HashSet<(G::Vertex, State)> = HashSet::new();
+        let mut queue: VecDeque<(G::Vertex, State)> = VecDeque::new();
+
+        visited.insert((source, dfa.initial));
+        queue.push_back((source, dfa.initial));
+
+        while let Some((vertex, state)) = queue.pop_front() {
+            if dfa.finals.contains(&state) {
+                ret.insert((source, vertex));
+            }
+
+            for (symbol, next_vertex) in graph.successors(vertex) {
+                if let Some(&next_state) = dfa.next[state].get(&symbol) {
+                    if visited.insert((next_vertex, next_state)) {
+                        queue.push_back((next_vertex, next_state));
+                    }
+                }
+            }
+        }
+    }
+
+    ret
+}
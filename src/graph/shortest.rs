@@ -0,0 +1,163 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::{State, Symbol, DFA};
+
+use super::WeightedLabeledGraph;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstrainedPath<V, W> {
+    pub cost: W,
+    pub vertices: Vec<V>,
+    pub symbols: Vec<Symbol>,
+    pub states: Vec<State>,
+}
+
+type Queue<W> = BinaryHeap<Reverse<(W, W, usize)>>;
+
+struct Entry<V, W> {
+    vertex: V,
+    state: State,
+    cost: W,
+    edge: Option<(Symbol, usize)>,
+}
+
+fn trace<V: Copy, W: Copy>(arena: &[Entry<V, W>], mut id: usize) -> ConstrainedPath<V, W> {
+    let cost = arena[id].cost;
+    let (mut vertices, mut symbols, mut states) = (Vec::new(), Vec::new(), Vec::new());
+
+    loop {
+        vertices.push(arena[id].vertex);
+        states.push(arena[id].state);
+        match arena[id].edge {
+            Some((symbol, parent)) => {
+                symbols.push(symbol);
+                id = parent;
+            }
+            None => break,
+        }
+    }
+
+    vertices.reverse();
+    symbols.reverse();
+    states.reverse();
+
+    ConstrainedPath {
+        cost,
+        vertices,
+        symbols,
+        states,
+    }
+}
+
+// Runs A* over the product of the graph and the DFA, settling every product node up to `k` times.
+// With k = 1 this is plain A* (or Dijkstra for a zero heuristic); for larger k it enumerates the
+// k cheapest walks to the target in increasing order of cost, vertices may repeat.
+fn search<G, H>(
+    graph: &G,
+    dfa: &DFA,
+    source: G::Vertex,
+    target: G::Vertex,
+    k: usize,
+    heuristic: H,
+) -> Vec<ConstrainedPath<G::Vertex, G::Weight>>
+where
+    G: WeightedLabeledGraph,
+    H: Fn(G::Vertex) -> G::Weight,
+{
+    let mut ret = Vec::new();
+    let mut arena: Vec<Entry<G::Vertex, G::Weight>> = Vec::new();
+    let mut settled: HashMap<(G::Vertex, State), usize> = HashMap::new();
+    let mut heap: Queue<G::Weight> = BinaryHeap::new();
+
+    let zero = G::Weight::default();
+    arena.push(Entry {
+        vertex: source,
+        state: dfa.initial,
+        cost: zero,
+        edge: None,
+    });
+    heap.push(Reverse((heuristic(source), zero, 0)));
+
+    while let Some(Reverse((_, cost, id))) = heap.pop() {
+        let (vertex, state) = (arena[id].vertex, arena[id].state);
+        let count = settled.entry((vertex, state)).or_default();
+        if *count >= k {
+            continue;
+        }
+        *count += 1;
+
+        if vertex == target && dfa.finals.contains(&state) {
+            ret.push(trace(&arena, id));
+            if ret.len() == k {
+                break;
+            }
+        }
+
+        for (symbol, next_vertex, weight) in graph.weighted_successors(vertex) {
+            let Some(&next_state) = dfa.next[state].get(&symbol) else {
+                continue;
+            };
+            if settled
+                .get(&(next_vertex, next_state))
+                .copied()
+                .unwrap_or(0)
+                >= k
+            {
+                continue;
+            }
+
+            let next_cost = cost + weight;
+            arena.push(Entry {
+                vertex: next_vertex,
+                state: next_state,
+                cost: next_cost,
+                edge: Some((symbol, id)),
+            });
+            heap.push(Reverse((
+                next_cost + heuristic(next_vertex),
+                next_cost,
+                arena.len() - 1,
+            )));
+        }
+    }
+
+    ret
+}
+
+pub fn shortest_path<G: WeightedLabeledGraph>(
+    graph: &G,
+    dfa: &DFA,
+    source: G::Vertex,
+    target: G::Vertex,
+) -> Option<ConstrainedPath<G::Vertex, G::Weight>> {
+    search(graph, dfa, source, target, 1, |_| G::Weight::default()).pop()
+}
+
+// The heuristic must be consistent (never overestimate, and obey the triangle inequality) for the
+// returned path to be the cheapest one.
+pub fn shortest_path_astar<G, H>(
+    graph: &G,
+    dfa: &DFA,
+    source: G::Vertex,
+    target: G::Vertex,
+    heuristic: H,
+) -> Option<ConstrainedPath<G::Vertex, G::Weight>>
+where
+    G: WeightedLabeledGraph,
+    H: Fn(G::Vertex) -> G::Weight,
+{
+    search(graph, dfa, source, target, 1, heuristic).pop()
+}
+
+pub fn k_shortest_paths<G: WeightedLabeledGraph>(
+    graph: &G,
+    dfa: &DFA,
+    source: G::Vertex,
+    target: G::Vertex,
+    k: usize,
+) -> Vec<ConstrainedPath<G::Vertex, G::Weight>> {
+    search(graph, dfa, source, target, k, |_| G::Weight::default())
+}
@@ -0,0 +1,108 @@
+use std::{error::Error, fmt::Display, ops::Range};
+
+use crate::{dfa::MultiDFA, Symbol, NFA};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Token<K> {
+    pub kind: K,
+    pub span: Range<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LexError {
+    pub position: usize,
+}
+
+impl Display for LexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no token matches at position {}", self.position)
+    }
+}
+
+impl Error for LexError {}
+
+// Splits input into tokens using the longest match of any rule (maximal munch); among rules
+// matching the same longest prefix, the earliest one wins. Empty matches are never tokens.
+#[derive(Clone, Debug)]
+pub struct Lexer<K> {
+    dfa: MultiDFA,
+    kinds: Vec<K>,
+}
+
+impl<K: Clone> Lexer<K> {
+    // Rules are byte regexes, see `NFA::from_byte_regex`.
+    pub fn new<'a>(rules: impl IntoIterator<Item = (&'a str, K)>) -> Result<Self, Box<dyn Error>> {
+        let rules = rules
+            .into_iter()
+            .map(|(regex, kind)| Ok((NFA::from_byte_regex(regex)?, kind)))
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+        Ok(Self::from_nfas(rules))
+    }
+
+    pub fn from_nfas(rules: impl IntoIterator<Item = (NFA, K)>) -> Self {
+        let (nfas, kinds): (Vec<NFA>, Vec<K>) = rules.into_iter().unzip();
+
+        Self {
+            dfa: MultiDFA::from_nfas(&nfas).minimize(),
+            kinds,
+        }
+    }
+
+    // The longest token starting at `start`.
+    fn next_token(&self, input: &[Symbol], start: usize) -> Result<Token<K>, LexError> {
+        let mut state = self.dfa.initial;
+        let mut ret = None;
+
+        for (i, symbol) in input.iter().enumerate().skip(start) {
+            match self.dfa.next[state].get(symbol) {
+                Some(&next_state) => state = next_state,
+                None => break,
+            }
+            if let Some(&rule) = self.dfa.tags.get(&state).and_then(|x| x.first()) {
+                ret = Some(Token {
+                    kind: self.kinds[rule].clone(),
+                    span: start..i + 1,
+                });
+            }
+        }
+
+        ret.ok_or(LexError { position: start })
+    }
+
+    pub fn tokens<'a>(&'a self, input: &'a [Symbol]) -> Tokens<'a, K> {
+        Tokens {
+            lexer: self,
+            input,
+            position: Some(0),
+        }
+    }
+
+    pub fn tokenize(&self, input: &[Symbol]) -> Result<Vec<Token<K>>, LexError> {
+        self.tokens(input).collect()
+    }
+
+    pub fn tokenize_str(&self, s: &str) -> Result<Vec<Token<K>>, LexError> {
+        let input: Vec<Symbol> = s.bytes().map(Symbol::from_byte).collect();
+        self.tokenize(&input)
+    }
+}
+
+// Yields tokens until the input is exhausted, or a single error at the first position where no
+// token matches.
+pub struct Tokens<'a, K> {
+    lexer: &'a Lexer<K>,
+    input: &'a [Symbol],
+    position: Option<usize>,
+}
+
+impl<'a, K: Clone> Iterator for Tokens<'a, K> {
+    type Item = Result<Token<K>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let position = self.position.filter(|&x| x < self.input.len())?;
+
+        let ret = self.lexer.next_token(self.input, position);
+        self.position = ret.as_ref().ok().map(|token| token.span.end);
+        Some(ret)
+    }
+}
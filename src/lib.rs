@@ -1,19 +1,58 @@
-use std::fmt::{Debug, Display};
+use std::{
+    fmt::{Debug, Display},
+    num::ParseIntError,
+    str::FromStr,
+};
 
 use nicole::IdLike;
 use nicole_derive::IdLike;
 
+pub mod att;
+pub mod cfl;
 pub mod dfa;
+pub mod dot;
+pub mod graph;
+pub mod lexer;
+mod limits;
 pub mod nfa;
+mod refine;
+pub mod symbolic;
+pub mod transducer;
+
+#[cfg(feature = "serde")]
+mod serialization;
 
 #[cfg(test)]
 mod test;
 
-pub use crate::{dfa::DFA, nfa::NFA};
+pub use crate::{
+    att::SymbolTable,
+    dfa::{
+        Cursor, DenseDFA, DfaRef, Finder, LazyDFA, LiveDFA, MatchKind, MultiDFA, StepResult,
+        SymbolClasses, DFA,
+    },
+    dot::DotOptions,
+    graph::{LabeledGraph, WeightedLabeledGraph},
+    lexer::{LexError, Lexer, Token},
+    limits::{LimitError, Limits},
+    nfa::{BitNFA, LiveNFA, NfaCursor, NFA},
+    symbolic::{SymbolSet, SymbolicDFA, SymbolicNFA},
+    transducer::{Fst, Mealy, Moore},
+};
 
 #[derive(Default, Clone, Copy, Hash, Eq, IdLike, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct State(i32);
 #[derive(Default, Clone, Copy, Hash, Eq, IdLike, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct Symbol(i32);
 
 impl Display for State {
@@ -56,4 +95,48 @@ impl Symbol {
     pub fn from_u8(c: u8) -> Self {
         Symbol((c - 97) as i32)
     }
+
+    pub fn from_byte(b: u8) -> Self {
+        Symbol(b as i32)
+    }
+}
+
+// Converts a parsed state id, rejecting ids that do not fit in a `State`.
+pub(crate) fn state_from_id(id: usize) -> Result<State, String> {
+    if id > i32::MAX as usize {
+        Err(format!("state id {} is too large", id))
+    } else {
+        Ok(State::from(id))
+    }
+}
+
+// Checks a state count from untrusted input before tables are sized from it.
+pub(crate) fn check_state_count(states: usize) -> Result<usize, String> {
+    if states > i32::MAX as usize {
+        Err(format!("state count {} is too large", states))
+    } else {
+        Ok(states)
+    }
+}
+
+pub(crate) fn check_state(state: State, states: usize) -> Result<State, String> {
+    if state.0 < 0 || usize::from(state) >= states {
+        Err(format!("state {} out of range (0..{})", state, states))
+    } else {
+        Ok(state)
+    }
+}
+
+impl FromStr for Symbol {
+    type Err = ParseIntError;
+
+    // Inverse of `Display`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some('ε'), None) => Ok(Symbol::null()),
+            (Some(c @ 'a'..='{'), None) => Ok(Symbol(c as i32 - 97)),
+            _ => s.parse().map(Symbol),
+        }
+    }
 }
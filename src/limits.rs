@@ -0,0 +1,45 @@
+use std::{error::Error, fmt::Display};
+
+// Bounds on the size of automata built from untrusted input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Limits {
+    pub max_states: usize,
+    pub max_transitions: usize,
+}
+
+impl Limits {
+    pub const UNLIMITED: Self = Self {
+        max_states: usize::MAX,
+        max_transitions: usize::MAX,
+    };
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_states: 100_000,
+            max_transitions: 10_000_000,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LimitError {
+    TooManyStates { limit: usize },
+    TooManyTransitions { limit: usize },
+}
+
+impl Display for LimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitError::TooManyStates { limit } => {
+                write!(f, "automaton exceeds the limit of {} states", limit)
+            }
+            LimitError::TooManyTransitions { limit } => {
+                write!(f, "automaton exceeds the limit of {} transitions", limit)
+            }
+        }
+    }
+}
+
+impl Error for LimitError {}
@@ -1,15 +1,21 @@
 use std::{
     collections::{BTreeSet, HashMap, HashSet},
     error::Error,
-    iter,
+    io, iter,
     str::FromStr,
 };
 
-use crate::{State, Symbol};
+use crate::{
+    att, check_state, check_state_count, dot, DotOptions, Limits, State, StepResult, Symbol,
+    SymbolTable,
+};
 
-use nicole::typedvec::TypedVec;
+use nicole::{typedvec::TypedVec, IdLike};
 
 mod regex_parser;
+mod simulate;
+
+pub use self::simulate::{BitNFA, LiveNFA, NfaCursor};
 
 pub type NextElem = HashMap<Symbol, BTreeSet<State>>;
 pub type NextElems = TypedVec<State, NextElem>;
@@ -25,52 +31,148 @@ impl FromStr for NFA {
     type Err = Box<dyn Error>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let next = regex_parser::parse(s)?;
+        Self::from_regex_with_limits(s, Limits::UNLIMITED)
+    }
+}
+
+impl NFA {
+    fn from_regex_next(next: NextElems) -> Self {
         let initials = iter::once(State(0)).collect();
         let finals = iter::once(State::from(next.len().wrapping_sub(1))).collect();
 
-        Ok(Self {
+        Self {
             next,
             initials,
             finals,
+        }
+    }
+
+    // Fails with a boxed `LimitError` if the NFA would exceed `limits`, which is checked before
+    // `{min,max}` ranges are unrolled.
+    pub fn from_regex_with_limits(s: &str, limits: Limits) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::from_regex_next(regex_parser::parse(s, limits)?))
+    }
+
+    // Parses a regex over arbitrary characters, compiled to UTF-8 byte symbols (see `Symbol::from_byte`).
+    pub fn from_byte_regex(s: &str) -> Result<Self, Box<dyn Error>> {
+        Self::from_byte_regex_with_limits(s, Limits::UNLIMITED)
+    }
+
+    pub fn from_byte_regex_with_limits(s: &str, limits: Limits) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::from_regex_next(regex_parser::parse_bytes(s, limits)?))
+    }
+
+    // Builds an NFA from a transition list, checking that all states are in range.
+    pub(crate) fn from_transitions(
+        states: usize,
+        initials: impl IntoIterator<Item = State>,
+        finals: impl IntoIterator<Item = State>,
+        transitions: impl IntoIterator<Item = (State, Symbol, State)>,
+    ) -> Result<Self, String> {
+        let states = check_state_count(states)?;
+        let mut next = NextElems::new();
+        next.try_reserve_exact(states)
+            .map_err(|_| format!("cannot allocate {} states", states))?;
+        next.resize_with(states, HashMap::new);
+
+        for (state, symbol, next_state) in transitions {
+            let state = check_state(state, states)?;
+            let next_state = check_state(next_state, states)?;
+            next[state].entry(symbol).or_default().insert(next_state);
+        }
+
+        Ok(Self {
+            next,
+            initials: initials
+                .into_iter()
+                .map(|x| check_state(x, states))
+                .collect::<Result<_, _>>()?,
+            finals: finals
+                .into_iter()
+                .map(|x| check_state(x, states))
+                .collect::<Result<_, _>>()?,
         })
     }
-}
 
-impl NFA {
-    pub fn print_graphviz(&self) {
-        indoc::printdoc!(
-            "
-            digraph finite_state_machine {{
-                rankdir=LR;
-                size=\"8,5\"
-                node [shape = doublecircle]; q{terminal_id};
-                node [shape = circle];
-        ",
-            terminal_id = self.next.len() - 1
-        );
-
-        println!();
-
-        for state_id in 0..self.next.len() {
-            println!(
-                "    q{state} [ label=<q<SUB>{state}</SUB>> ];",
-                state = state_id
-            );
+    pub fn from_dot(s: &str) -> Result<Self, Box<dyn Error>> {
+        let parsed = dot::parse_dot(s)?;
+        Ok(Self::from_transitions(
+            parsed.state_count,
+            parsed.initials,
+            parsed.finals,
+            parsed.edges,
+        )?)
+    }
+
+    pub fn read_att(
+        r: impl io::BufRead,
+        symbols: Option<&SymbolTable>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let parsed = att::read_att(r, symbols)?;
+        Ok(Self::from_transitions(
+            parsed.state_count,
+            parsed.start,
+            parsed.finals,
+            parsed.edges,
+        )?)
+    }
+
+    // The AT&T format has a single start state, so several initial states are written as a fresh
+    // start state with epsilon transitions to each of them.
+    pub fn write_att<W: io::Write>(&self, w: W, symbols: Option<&SymbolTable>) -> io::Result<()> {
+        let edges = self.next.iter().flat_map(|(state, dict)| {
+            dict.iter().flat_map(move |(&symbol, next_states)| {
+                next_states
+                    .iter()
+                    .map(move |&next_state| (state, symbol, next_state))
+            })
+        });
+
+        match self.initials.iter().collect::<Vec<_>>()[..] {
+            [] => Ok(()),
+            [&initial] => att::write_att(w, initial, &self.finals, edges, symbols),
+            ref initials => {
+                let start = State::from(self.next.len());
+                let start_edges = initials
+                    .iter()
+                    .map(|&&initial| (start, Symbol::null(), initial));
+                att::write_att(w, start, &self.finals, edges.chain(start_edges), symbols)
+            }
         }
+    }
+
+    pub fn write_dot<W: io::Write>(&self, w: W, options: &DotOptions) -> io::Result<()> {
+        dot::write_dot(
+            w,
+            self.next.len(),
+            self.initials.iter().copied(),
+            self.finals.iter().copied(),
+            self.next.iter().flat_map(|(state, dict)| {
+                dict.iter().flat_map(move |(&symbol, next_states)| {
+                    next_states
+                        .iter()
+                        .map(move |&next_state| (state, symbol, next_state))
+                })
+            }),
+            options,
+        )
+    }
+
+    pub fn matches(&self, string: &[Symbol]) -> bool {
+        let live = LiveNFA::new(self);
+        let mut cursor = live.start();
 
-        for (state, dict) in self.next.iter() {
-            for (symbol, next_states) in dict.iter() {
-                for next_state in next_states {
-                    println!(
-                        "    {state} -> {next_state} [ label={symbol} ];",
-                        state = state,
-                        next_state = next_state,
-                        symbol = symbol
-                    );
-                }
+        for &symbol in string {
+            if cursor.step(symbol) == StepResult::Dead {
+                return false;
             }
         }
-        println!("}}");
+
+        cursor.is_accepting()
+    }
+
+    pub fn print_graphviz(&self) {
+        self.write_dot(io::stdout().lock(), &DotOptions::default())
+            .unwrap();
     }
 }
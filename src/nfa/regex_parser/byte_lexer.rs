@@ -0,0 +1,208 @@
+use std::{
+    fmt::{self, Display},
+    iter::Peekable,
+    str::CharIndices,
+};
+
+use super::utf8;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tok {
+    Pipe,
+    Star,
+    Question,
+    LParen,
+    RParen,
+    Dot,
+    Char(char),
+    Class(Vec<(u32, u32)>),
+    Repeat((i32, i32)),
+}
+
+impl Display for Tok {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tok::Pipe => write!(f, "|"),
+            Tok::Star => write!(f, "*"),
+            Tok::Question => write!(f, "?"),
+            Tok::LParen => write!(f, "("),
+            Tok::RParen => write!(f, ")"),
+            Tok::Dot => write!(f, "."),
+            Tok::Char(c) => write!(f, "{:?}", c),
+            Tok::Class(_) => write!(f, "[...]"),
+            Tok::Repeat((min, max)) => write!(f, "{{{},{}}}", min, max),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LexError {
+    pub location: usize,
+    pub message: String,
+}
+
+impl Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.location)
+    }
+}
+
+pub type Spanned = Result<(usize, Tok, usize), LexError>;
+
+pub struct Lexer<'input> {
+    input: &'input str,
+    chars: Peekable<CharIndices<'input>>,
+}
+
+impl<'input> Lexer<'input> {
+    pub fn new(input: &'input str) -> Self {
+        Self {
+            input,
+            chars: input.char_indices().peekable(),
+        }
+    }
+
+    fn offset(&mut self) -> usize {
+        self.chars.peek().map_or(self.input.len(), |&(i, _)| i)
+    }
+
+    fn error<T>(&mut self, message: &str) -> Result<T, LexError> {
+        Err(LexError {
+            location: self.offset(),
+            message: message.to_string(),
+        })
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), LexError> {
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            _ => self.error(&format!("expected {:?}", expected)),
+        }
+    }
+
+    fn hex(&mut self, min_digits: usize, max_digits: usize) -> Result<u32, LexError> {
+        let mut digits = String::new();
+        while let Some(&(_, c)) = self.chars.peek() {
+            if digits.len() == max_digits || !c.is_ascii_hexdigit() {
+                break;
+            }
+            digits.push(c);
+            self.chars.next();
+        }
+        if digits.len() < min_digits {
+            return self.error("expected hexadecimal digits");
+        }
+        Ok(u32::from_str_radix(&digits, 16).unwrap())
+    }
+
+    fn number(&mut self) -> Result<i32, LexError> {
+        let mut digits = String::new();
+        while let Some(&(_, c)) = self.chars.peek() {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            digits.push(c);
+            self.chars.next();
+        }
+        match digits.parse() {
+            Ok(x) => Ok(x),
+            Err(_) => self.error("expected a number"),
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, LexError> {
+        let c = match self.chars.next() {
+            Some((_, 'n')) => '\n',
+            Some((_, 'r')) => '\r',
+            Some((_, 't')) => '\t',
+            Some((_, '0')) => '\0',
+            Some((_, 'x')) => {
+                let x = self.hex(2, 2)?;
+                char::from_u32(x).unwrap()
+            }
+            Some((_, 'u')) => {
+                self.expect('{')?;
+                let x = self.hex(1, 6)?;
+                self.expect('}')?;
+                match char::from_u32(x) {
+                    Some(c) => c,
+                    None => return self.error("invalid unicode scalar value"),
+                }
+            }
+            Some((_, c)) if !c.is_alphanumeric() => c,
+            Some(_) => return self.error("unknown escape sequence"),
+            None => return self.error("unterminated escape sequence"),
+        };
+        Ok(c)
+    }
+
+    fn class_char(&mut self) -> Result<char, LexError> {
+        match self.chars.next() {
+            Some((_, '\\')) => self.escape(),
+            Some((_, c)) => Ok(c),
+            None => self.error("unterminated character class"),
+        }
+    }
+
+    fn class(&mut self) -> Result<Tok, LexError> {
+        let negated = self.chars.next_if(|&(_, c)| c == '^').is_some();
+        let mut ranges = Vec::new();
+
+        loop {
+            if self.chars.next_if(|&(_, c)| c == ']').is_some() {
+                break;
+            }
+            let start = self.class_char()?;
+            let end = if self.chars.next_if(|&(_, c)| c == '-').is_some() {
+                self.class_char()?
+            } else {
+                start
+            };
+            if start > end {
+                return self.error("invalid character class range");
+            }
+            ranges.push((start as u32, end as u32));
+        }
+
+        if negated {
+            ranges = utf8::negate(&ranges);
+        }
+        if ranges.is_empty() {
+            return self.error("empty character class");
+        }
+        Ok(Tok::Class(ranges))
+    }
+
+    fn repeat(&mut self) -> Result<Tok, LexError> {
+        let min = self.number()?;
+        self.expect(',')?;
+        let max = self.number()?;
+        self.expect('}')?;
+        if min > max {
+            return self.error("invalid repetition range");
+        }
+        Ok(Tok::Repeat((min, max)))
+    }
+}
+
+impl<'input> Iterator for Lexer<'input> {
+    type Item = Spanned;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, c) = self.chars.next()?;
+        let tok = match c {
+            '|' => Ok(Tok::Pipe),
+            '*' => Ok(Tok::Star),
+            '?' => Ok(Tok::Question),
+            '(' => Ok(Tok::LParen),
+            ')' => Ok(Tok::RParen),
+            '.' => Ok(Tok::Dot),
+            '[' => self.class(),
+            '{' => self.repeat(),
+            '\\' => self.escape().map(Tok::Char),
+            ']' | '}' => self.error("unbalanced bracket"),
+            c => Ok(Tok::Char(c)),
+        };
+        Some(tok.map(|tok| (start, tok, self.offset())))
+    }
+}
@@ -2,12 +2,17 @@ use std::{error::Error, iter};
 
 use lalrpop_util::lalrpop_mod;
 
-use crate::{nfa, State};
+use crate::{nfa, LimitError, Limits, State};
 
 lalrpop_mod!(#[allow(clippy::all)] pub parser, "/src/nfa/regex_parser/parser.rs");
+lalrpop_mod!(#[allow(clippy::all)] pub byte_parser, "/src/nfa/regex_parser/byte_parser.rs");
+mod byte_lexer;
 mod parser_utils;
+mod utf8;
 
-use self::parser_utils::{add_e_transfer, backpatch, new_dummy_node, new_node, Ast};
+use self::parser_utils::{
+    add_e_transfer, backpatch, new_byte_range_node, new_dummy_node, new_node, Ast,
+};
 
 fn parse_rec(ast: &Ast, acc: &mut nfa::NextElems) -> (State, State) {
     let start = new_dummy_node(acc);
@@ -15,6 +20,9 @@ fn parse_rec(ast: &Ast, acc: &mut nfa::NextElems) -> (State, State) {
         &Ast::Terminal(id) => {
             new_node(acc, id, iter::once(State(start.0 + 2)).collect());
         }
+        &Ast::ByteRange(lo, hi) => {
+            new_byte_range_node(acc, lo, hi, iter::once(State(start.0 + 2)).collect());
+        }
         Ast::Cons(a1, a2) => {
             parse_rec(a1, acc);
             parse_rec(a2, acc);
@@ -53,12 +61,90 @@ fn parse_rec(ast: &Ast, acc: &mut nfa::NextElems) -> (State, State) {
     (start, (acc.len() - 1).into())
 }
 
-pub fn parse(regex: &str) -> Result<nfa::NextElems, Box<dyn Error>> {
-    let ast = parser::RegexParser::new()
-        .parse(regex)
-        .map_err(|x| x.to_string())?;
+// The number of states `compile` creates for the AST, saturating on overflow. This is checked
+// before compiling, since `{min,max}` unrolls `max` copies of its operand.
+fn state_count(ast: &Ast) -> usize {
+    fn state_count_rec(ast: &Ast) -> usize {
+        match ast {
+            Ast::Terminal(_) | Ast::ByteRange(_, _) => 2,
+            Ast::Cons(a1, a2) => 1usize
+                .saturating_add(state_count_rec(a1))
+                .saturating_add(state_count_rec(a2)),
+            Ast::Star(a) | Ast::Optional(a) => state_count_rec(a).saturating_add(2),
+            Ast::Or(a1, a2) => state_count_rec(a1)
+                .saturating_add(state_count_rec(a2))
+                .saturating_add(2),
+            &Ast::Range(ref a, min, max) => {
+                let (min, max) = (min.max(0) as usize, max.max(0) as usize);
+                let count = state_count_rec(a);
+                1usize
+                    .saturating_add(min.saturating_mul(count.saturating_add(1)))
+                    .saturating_add(
+                        max.saturating_sub(min)
+                            .saturating_mul(count.saturating_add(2)),
+                    )
+            }
+        }
+    }
+
+    state_count_rec(ast).saturating_add(1)
+}
+
+// The number of transitions `compile` creates for the AST, epsilon transitions included, saturating
+// on overflow. Each state from `new_dummy_node` has one, and every `add_e_transfer` adds another.
+fn transition_count(ast: &Ast) -> usize {
+    match ast {
+        Ast::Terminal(_) => 2,
+        &Ast::ByteRange(lo, hi) => 2 + hi.saturating_sub(lo) as usize,
+        Ast::Cons(a1, a2) => 1usize
+            .saturating_add(transition_count(a1))
+            .saturating_add(transition_count(a2)),
+        Ast::Star(a) => transition_count(a).saturating_add(4),
+        Ast::Or(a1, a2) => transition_count(a1)
+            .saturating_add(transition_count(a2))
+            .saturating_add(3),
+        Ast::Optional(a) => transition_count(a).saturating_add(3),
+        &Ast::Range(ref a, min, max) => {
+            let (min, max) = (min.max(0) as usize, max.max(0) as usize);
+            let count = transition_count(a);
+            1usize
+                .saturating_add(min.saturating_mul(count.saturating_add(1)))
+                .saturating_add(
+                    max.saturating_sub(min)
+                        .saturating_mul(count.saturating_add(3)),
+                )
+        }
+    }
+}
+
+fn compile(ast: &Ast, limits: Limits) -> Result<nfa::NextElems, LimitError> {
+    if state_count(ast) > limits.max_states {
+        return Err(LimitError::TooManyStates {
+            limit: limits.max_states,
+        });
+    }
+    if transition_count(ast) > limits.max_transitions {
+        return Err(LimitError::TooManyTransitions {
+            limit: limits.max_transitions,
+        });
+    }
+
     let mut ret: nfa::NextElems = nfa::NextElems::new();
-    parse_rec(&ast, &mut ret);
+    parse_rec(ast, &mut ret);
     ret.push(nfa::NextElem::new());
     Ok(ret)
 }
+
+pub fn parse(regex: &str, limits: Limits) -> Result<nfa::NextElems, Box<dyn Error>> {
+    let ast = parser::RegexParser::new()
+        .parse(regex)
+        .map_err(|x| x.to_string())?;
+    Ok(compile(&ast, limits)?)
+}
+
+pub fn parse_bytes(regex: &str, limits: Limits) -> Result<nfa::NextElems, Box<dyn Error>> {
+    let ast = byte_parser::RegexParser::new()
+        .parse(byte_lexer::Lexer::new(regex))
+        .map_err(|x| x.to_string())?;
+    Ok(compile(&ast, limits)?)
+}
@@ -4,9 +4,12 @@ use crate::{nfa, State, Symbol};
 
 use nicole::IdLike;
 
+use super::utf8;
+
 #[derive(Debug)]
 pub enum Ast {
     Terminal(Symbol),
+    ByteRange(u8, u8),
     Cons(Box<Ast>, Box<Ast>),
     Star(Box<Ast>),
     Or(Box<Ast>, Box<Ast>),
@@ -14,6 +17,34 @@ pub enum Ast {
     Range(Box<Ast>, i32, i32),
 }
 
+pub fn char_literal(c: char) -> Box<Ast> {
+    let mut buf = [0; 4];
+    c.encode_utf8(&mut buf)
+        .bytes()
+        .map(|b| Box::new(Ast::Terminal(Symbol::from_byte(b))))
+        .rev()
+        .reduce(|acc, x| Box::new(Ast::Cons(x, acc)))
+        .unwrap()
+}
+
+pub fn char_class(ranges: &[(u32, u32)]) -> Box<Ast> {
+    ranges
+        .iter()
+        .flat_map(|&(start, end)| utf8::sequences(start, end))
+        .map(|seq| {
+            seq.into_iter()
+                .map(|(lo, hi)| Box::new(Ast::ByteRange(lo, hi)))
+                .rev()
+                .reduce(|acc, x| Box::new(Ast::Cons(x, acc)))
+                .unwrap()
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .reduce(|acc, x| Box::new(Ast::Or(x, acc)))
+        .unwrap()
+}
+
 pub fn new_node(acc: &mut nfa::NextElems, symbol: Symbol, next: BTreeSet<State>) -> State {
     let len: State = acc.len().into();
     let mut hmap = nfa::NextElem::new();
@@ -22,6 +53,21 @@ pub fn new_node(acc: &mut nfa::NextElems, symbol: Symbol, next: BTreeSet<State>)
     len
 }
 
+pub fn new_byte_range_node(
+    acc: &mut nfa::NextElems,
+    lo: u8,
+    hi: u8,
+    next: BTreeSet<State>,
+) -> State {
+    let len: State = acc.len().into();
+    acc.push(
+        (lo..=hi)
+            .map(|b| (Symbol::from_byte(b), next.clone()))
+            .collect(),
+    );
+    len
+}
+
 pub fn new_dummy_node(acc: &mut nfa::NextElems) -> State {
     new_node(
         acc,
@@ -0,0 +1,89 @@
+const MAX_SCALAR: u32 = char::MAX as u32;
+const SURROGATE_START: u32 = 0xD800;
+const SURROGATE_END: u32 = 0xDFFF;
+
+pub type Utf8Sequence = Vec<(u8, u8)>;
+
+fn encode(c: u32, buf: &mut [u8; 4]) -> usize {
+    char::from_u32(c).unwrap().encode_utf8(buf).len()
+}
+
+// Splits a range of scalar values into sequences of byte ranges, in the style of utf8-ranges.
+// Every scalar in [start, end] (surrogates excluded) is matched by exactly one sequence.
+pub fn sequences(start: u32, end: u32) -> Vec<Utf8Sequence> {
+    let mut ret = Vec::new();
+    let mut stack = vec![(start, end.min(MAX_SCALAR))];
+
+    'top: while let Some((mut start, mut end)) = stack.pop() {
+        'inner: loop {
+            if start < SURROGATE_START && end > SURROGATE_END {
+                stack.push((SURROGATE_END + 1, end));
+                end = SURROGATE_START - 1;
+            } else if (SURROGATE_START..=SURROGATE_END).contains(&start) {
+                start = SURROGATE_END + 1;
+            } else if (SURROGATE_START..=SURROGATE_END).contains(&end) {
+                end = SURROGATE_START - 1;
+            }
+
+            if start > end {
+                continue 'top;
+            }
+
+            for max in [0x7F, 0x7FF, 0xFFFF] {
+                if start <= max && max < end {
+                    stack.push((max + 1, end));
+                    end = max;
+                    continue 'inner;
+                }
+            }
+
+            if end <= 0x7F {
+                ret.push(vec![(start as u8, end as u8)]);
+                continue 'top;
+            }
+
+            for i in 1..4 {
+                let mask: u32 = (1 << (6 * i)) - 1;
+                if start & !mask != end & !mask {
+                    if start & mask != 0 {
+                        stack.push(((start | mask) + 1, end));
+                        end = start | mask;
+                        continue 'inner;
+                    }
+                    if end & mask != mask {
+                        stack.push((end & !mask, end));
+                        end = (end & !mask) - 1;
+                        continue 'inner;
+                    }
+                }
+            }
+
+            let (mut start_buf, mut end_buf) = ([0; 4], [0; 4]);
+            let len = encode(start, &mut start_buf);
+            encode(end, &mut end_buf);
+            ret.push((0..len).map(|i| (start_buf[i], end_buf[i])).collect());
+            continue 'top;
+        }
+    }
+
+    ret
+}
+
+pub fn negate(ranges: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut sorted = ranges.to_vec();
+    sorted.sort_unstable();
+
+    let mut ret = Vec::new();
+    let mut next = 0;
+    for (start, end) in sorted {
+        if start > next {
+            ret.push((next, start - 1));
+        }
+        next = next.max(end.saturating_add(1));
+    }
+    if next <= MAX_SCALAR {
+        ret.push((next, MAX_SCALAR));
+    }
+
+    ret
+}
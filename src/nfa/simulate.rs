@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+
+use nicole::{typedvec::TypedVec, IdLike};
+
+use crate::{dfa::live_states, State, StepResult, Symbol, NFA};
+
+// A set of states with O(1) insertion, membership and clearing, and iteration in insertion order.
+#[derive(Clone, Debug)]
+struct SparseSet {
+    dense: Vec<State>,
+    sparse: Vec<usize>,
+}
+
+impl SparseSet {
+    fn new(capacity: usize) -> Self {
+        Self {
+            dense: Vec::with_capacity(capacity),
+            sparse: vec![0; capacity],
+        }
+    }
+
+    fn contains(&self, state: State) -> bool {
+        let i = self.sparse[usize::from(state)];
+        i < self.dense.len() && self.dense[i] == state
+    }
+
+    fn insert(&mut self, state: State) -> bool {
+        if self.contains(state) {
+            return false;
+        }
+        self.sparse[usize::from(state)] = self.dense.len();
+        self.dense.push(state);
+        true
+    }
+
+    fn clear(&mut self) {
+        self.dense.clear();
+    }
+}
+
+// An NFA along with its live states, like `LiveDFA`.
+#[derive(Clone, Debug)]
+pub struct LiveNFA<'a> {
+    nfa: &'a NFA,
+    live: TypedVec<State, bool>,
+}
+
+impl<'a> LiveNFA<'a> {
+    pub fn new(nfa: &'a NFA) -> Self {
+        let edges = nfa.next.iter().flat_map(|(state, dict)| {
+            dict.values()
+                .flatten()
+                .map(move |&next_state| (state, next_state))
+        });
+
+        Self {
+            nfa,
+            live: live_states(nfa.next.len(), nfa.finals.iter().copied(), edges),
+        }
+    }
+
+    pub fn is_live(&self, state: State) -> bool {
+        self.live[state]
+    }
+
+    pub fn start(&self) -> NfaCursor<'_> {
+        let mut ret = NfaCursor {
+            nfa: self.nfa,
+            live: &self.live,
+            current: SparseSet::new(self.nfa.next.len()),
+            next: SparseSet::new(self.nfa.next.len()),
+            stack: Vec::new(),
+        };
+        ret.reset();
+        ret
+    }
+}
+
+// Simulates an NFA one symbol at a time, tracking the epsilon-closed set of current live states,
+// so that it dies as soon as no final state is reachable.
+#[derive(Clone, Debug)]
+pub struct NfaCursor<'a> {
+    nfa: &'a NFA,
+    live: &'a TypedVec<State, bool>,
+    current: SparseSet,
+    next: SparseSet,
+    stack: Vec<State>,
+}
+
+impl<'a> NfaCursor<'a> {
+    // Adds the states on the stack and everything reachable from them by epsilon transitions.
+    // States that are not live are skipped, along with their closure, which cannot be live either.
+    fn close(nfa: &NFA, live: &TypedVec<State, bool>, set: &mut SparseSet, stack: &mut Vec<State>) {
+        while let Some(state) = stack.pop() {
+            if live[state] && set.insert(state) {
+                if let Some(next_states) = nfa.next[state].get(&Symbol::null()) {
+                    stack.extend(next_states);
+                }
+            }
+        }
+    }
+
+    pub fn step(&mut self, symbol: Symbol) -> StepResult {
+        self.next.clear();
+        for &state in self.current.dense.iter() {
+            if let Some(next_states) = self.nfa.next[state].get(&symbol) {
+                self.stack.extend(next_states);
+                Self::close(self.nfa, self.live, &mut self.next, &mut self.stack);
+            }
+        }
+        std::mem::swap(&mut self.current, &mut self.next);
+
+        if self.is_accepting() {
+            StepResult::Accepting
+        } else if self.current.dense.is_empty() {
+            StepResult::Dead
+        } else {
+            StepResult::Alive
+        }
+    }
+
+    pub fn is_accepting(&self) -> bool {
+        self.current
+            .dense
+            .iter()
+            .any(|state| self.nfa.finals.contains(state))
+    }
+
+    pub fn states(&self) -> &[State] {
+        &self.current.dense
+    }
+
+    pub fn reset(&mut self) {
+        self.current.clear();
+        self.stack.extend(self.nfa.initials.iter());
+        Self::close(self.nfa, self.live, &mut self.current, &mut self.stack);
+    }
+}
+
+// Simulates an NFA with at most 64 states, with the set of current states as the bits of a u64.
+#[derive(Clone, Debug)]
+pub struct BitNFA {
+    initial: u64,
+    finals: u64,
+    // For each symbol and state, the epsilon-closed set of successors.
+    next: HashMap<Symbol, Vec<u64>>,
+}
+
+fn mask(states: impl IntoIterator<Item = State>) -> u64 {
+    states
+        .into_iter()
+        .fold(0, |acc, state| acc | 1 << usize::from(state))
+}
+
+impl BitNFA {
+    pub const MAX_STATES: usize = 64;
+
+    // Returns `None` if the NFA has too many states.
+    pub fn new(nfa: &NFA) -> Option<Self> {
+        let state_count = nfa.next.len();
+        if state_count > Self::MAX_STATES {
+            return None;
+        }
+
+        let mut closures: Vec<u64> = (0..state_count).map(|x| 1 << x).collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (state, dict) in nfa.next.iter() {
+                let closure = dict
+                    .get(&Symbol::null())
+                    .into_iter()
+                    .flatten()
+                    .fold(closures[usize::from(state)], |acc, &x| {
+                        acc | closures[usize::from(x)]
+                    });
+                changed |= closure != closures[usize::from(state)];
+                closures[usize::from(state)] = closure;
+            }
+        }
+        let close = |states: u64| {
+            (0..state_count)
+                .filter(|&x| states & 1 << x != 0)
+                .fold(0, |acc, x| acc | closures[x])
+        };
+
+        let mut next: HashMap<Symbol, Vec<u64>> = HashMap::new();
+        for (state, dict) in nfa.next.iter() {
+            for (&symbol, next_states) in dict.iter().filter(|(x, _)| !x.is_null()) {
+                next.entry(symbol).or_insert_with(|| vec![0; state_count])[usize::from(state)] =
+                    close(mask(next_states.iter().copied()));
+            }
+        }
+
+        Some(Self {
+            initial: close(mask(nfa.initials.iter().copied())),
+            finals: mask(nfa.finals.iter().copied()),
+            next,
+        })
+    }
+
+    pub fn initial(&self) -> u64 {
+        self.initial
+    }
+
+    pub fn is_accepting(&self, states: u64) -> bool {
+        states & self.finals != 0
+    }
+
+    pub fn step(&self, mut states: u64, symbol: Symbol) -> u64 {
+        let Some(next) = self.next.get(&symbol) else {
+            return 0;
+        };
+
+        let mut ret = 0;
+        while states != 0 {
+            ret |= next[states.trailing_zeros() as usize];
+            states &= states - 1;
+        }
+        ret
+    }
+
+    pub fn matches(&self, string: &[Symbol]) -> bool {
+        let mut states = self.initial;
+
+        for &symbol in string {
+            states = self.step(states, symbol);
+            if states == 0 {
+                return false;
+            }
+        }
+
+        self.is_accepting(states)
+    }
+}
@@ -0,0 +1,32 @@
+use std::{collections::HashMap, hash::Hash};
+
+use nicole::typedvec::TypedVec;
+
+use crate::State;
+
+// Moore-style partition refinement. Starting from a single block, states are split by their
+// signature under the current blocks until the number of blocks is stable. Block ids are numbered
+// in order of their first state.
+pub(crate) fn refine<S: Hash + Eq>(
+    state_count: usize,
+    signature: impl Fn(&TypedVec<State, usize>, State) -> S,
+) -> TypedVec<State, usize> {
+    let mut blocks: TypedVec<State, usize> = TypedVec::new();
+    blocks.resize(state_count, 0);
+    let mut block_count = 0;
+
+    loop {
+        let mut translate: HashMap<S, usize> = HashMap::new();
+        let mut new_blocks: TypedVec<State, usize> = TypedVec::new();
+        for state in (0..state_count).map(State::from) {
+            let len = translate.len();
+            new_blocks.push(*translate.entry(signature(&blocks, state)).or_insert(len));
+        }
+
+        blocks = new_blocks;
+        if translate.len() == block_count {
+            return blocks;
+        }
+        block_count = translate.len();
+    }
+}
@@ -0,0 +1,136 @@
+// Serde support, behind the `serde` feature.
+//
+// `State` and `Symbol` serialize as plain integers. Automata serialize as:
+//
+//     DFA: { "states": 3, "initial": 0, "finals": [2], "transitions": [[0, 0, 1], [1, 1, 2]] }
+//     NFA: { "states": 3, "initials": [0], "finals": [2], "transitions": [[0, null, 1], [1, 1, 2]] }
+//
+// where every transition is a `[from, symbol, to]` triple, sorted, and `null` is the epsilon symbol
+// (NFA only). Deserialization rejects states out of range, a state count larger than the number of
+// states the transitions, initials and finals could mention, epsilon transitions in a DFA, and DFA
+// transitions that are nondeterministic.
+
+use std::{collections::BTreeSet, fmt::Display};
+
+use nicole::IdLike;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{State, Symbol, DFA, NFA};
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DfaRepr {
+    states: usize,
+    initial: State,
+    finals: BTreeSet<State>,
+    transitions: Vec<(State, Symbol, State)>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NfaRepr {
+    states: usize,
+    initials: BTreeSet<State>,
+    finals: BTreeSet<State>,
+    transitions: Vec<(State, Option<Symbol>, State)>,
+}
+
+fn custom<E: de::Error>(msg: impl Display) -> E {
+    E::custom(msg)
+}
+
+// A state mentioned nowhere is unreachable and useless, so a larger count can only come from
+// corrupt input, and is rejected before it is used to size the automaton.
+fn check_states<E: de::Error>(states: usize, mentioned: usize) -> Result<(), E> {
+    if states > mentioned {
+        Err(custom(format!(
+            "state count {} exceeds the {} states the automaton mentions",
+            states, mentioned
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+impl Serialize for DFA {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut transitions: Vec<(State, Symbol, State)> = self
+            .next
+            .iter()
+            .flat_map(|(state, dict)| {
+                dict.iter()
+                    .map(move |(&symbol, &next_state)| (state, symbol, next_state))
+            })
+            .collect();
+        transitions.sort();
+
+        DfaRepr {
+            states: self.next.len(),
+            initial: self.initial,
+            finals: self.finals.iter().copied().collect(),
+            transitions,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DFA {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = DfaRepr::deserialize(deserializer)?;
+        check_states(
+            repr.states,
+            2 * repr.transitions.len() + 1 + repr.finals.len(),
+        )?;
+        DFA::from_transitions(repr.states, repr.initial, repr.finals, repr.transitions)
+            .map_err(custom)
+    }
+}
+
+impl Serialize for NFA {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut transitions: Vec<(State, Option<Symbol>, State)> = self
+            .next
+            .iter()
+            .flat_map(|(state, dict)| {
+                dict.iter().flat_map(move |(&symbol, next_states)| {
+                    let symbol = if symbol.is_null() { None } else { Some(symbol) };
+                    next_states
+                        .iter()
+                        .map(move |&next_state| (state, symbol, next_state))
+                })
+            })
+            .collect();
+        transitions.sort();
+
+        NfaRepr {
+            states: self.next.len(),
+            initials: self.initials.iter().copied().collect(),
+            finals: self.finals.iter().copied().collect(),
+            transitions,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for NFA {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = NfaRepr::deserialize(deserializer)?;
+        check_states(
+            repr.states,
+            2 * repr.transitions.len() + repr.initials.len() + repr.finals.len(),
+        )?;
+        let mut transitions = Vec::with_capacity(repr.transitions.len());
+        for (state, symbol, next_state) in repr.transitions {
+            let symbol = match symbol {
+                Some(x) if x.is_null() => {
+                    return Err(custom("epsilon transitions must use null as their symbol"));
+                }
+                Some(x) => x,
+                None => Symbol::null(),
+            };
+            transitions.push((state, symbol, next_state));
+        }
+
+        NFA::from_transitions(repr.states, repr.initials, repr.finals, transitions).map_err(custom)
+    }
+}
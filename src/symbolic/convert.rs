@@ -0,0 +1,82 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+use nicole::typedvec::TypedVec;
+
+use crate::State;
+
+use super::{DfaNextElem, DfaNextElems, EpsilonElems, SymbolSet, SymbolicDFA, SymbolicNFA};
+
+pub fn e_closure(epsilon: &EpsilonElems) -> TypedVec<State, BTreeSet<State>> {
+    let mut ret: TypedVec<State, BTreeSet<State>> = TypedVec::new();
+    *ret = vec![BTreeSet::new(); epsilon.len()];
+
+    for (initial, _) in epsilon.iter() {
+        let mut stack = vec![initial];
+        while let Some(x) = stack.pop() {
+            if ret[initial].insert(x) {
+                stack.extend(epsilon[x].iter().copied());
+            }
+        }
+    }
+
+    ret
+}
+
+pub fn determinize(nfa: &SymbolicNFA) -> SymbolicDFA {
+    let mut queue: VecDeque<BTreeSet<State>> = VecDeque::new();
+    let mut translate: HashMap<BTreeSet<State>, State> = HashMap::new();
+    let mut ret = DfaNextElems::new();
+    let mut finals: HashSet<State> = HashSet::new();
+    let ecl = e_closure(&nfa.epsilon);
+    let initial: BTreeSet<State> = nfa
+        .initials
+        .iter()
+        .flat_map(|&x| ecl[x].iter().copied())
+        .collect();
+
+    queue.push_back(initial.clone());
+    translate.insert(initial, 0.into());
+    let mut max_state = 1;
+
+    while let Some(subset) = queue.pop_front() {
+        if subset.iter().any(|x| nfa.finals.contains(x)) {
+            finals.insert(translate[&subset]);
+        }
+
+        let transitions: Vec<&(SymbolSet, BTreeSet<State>)> = subset
+            .iter()
+            .flat_map(|&state| nfa.next[state].iter())
+            .collect();
+        let sets: Vec<&SymbolSet> = transitions.iter().map(|(set, _)| set).collect();
+
+        let mut by_target: BTreeMap<BTreeSet<State>, SymbolSet> = BTreeMap::new();
+        for (minterm, members) in SymbolSet::minterms(&sets) {
+            let target: BTreeSet<State> = members
+                .into_iter()
+                .flat_map(|i| transitions[i].1.iter())
+                .flat_map(|&x| ecl[x].iter().copied())
+                .collect();
+            let entry = by_target.entry(target).or_default();
+            *entry = entry.union(&minterm);
+        }
+
+        let mut row = DfaNextElem::new();
+        for (target, set) in by_target {
+            let next_state_id = translate.get(&target).copied().unwrap_or_else(|| {
+                queue.push_back(target.clone());
+                translate.insert(target, max_state.into());
+                max_state += 1;
+                (max_state - 1).into()
+            });
+            row.push((set, next_state_id));
+        }
+
+        ret.push(row);
+    }
+
+    SymbolicDFA {
+        next: ret,
+        initial: State(0),
+        finals,
+    }
+}
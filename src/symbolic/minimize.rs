@@ -0,0 +1,51 @@
+use std::collections::{BTreeMap, HashSet};
+
+use nicole::typedvec::TypedVec;
+
+use crate::{refine::refine, State};
+
+use super::{DfaNextElem, DfaNextElems, SymbolSet, SymbolicDFA};
+
+type Signature = (bool, BTreeMap<usize, SymbolSet>);
+
+fn signature(dfa: &SymbolicDFA, blocks: &TypedVec<State, usize>, state: State) -> Signature {
+    let mut by_block: BTreeMap<usize, SymbolSet> = BTreeMap::new();
+    for (set, next) in dfa.next[state].iter() {
+        let entry = by_block.entry(blocks[*next]).or_default();
+        *entry = entry.union(set);
+    }
+    (dfa.finals.contains(&state), by_block)
+}
+
+// Transitions are compared per target block, so states whose symbol sets are split differently
+// but lead to the same blocks are still merged.
+pub fn minimize(dfa: &SymbolicDFA) -> SymbolicDFA {
+    let blocks = refine(dfa.next.len(), |blocks, state| {
+        signature(dfa, blocks, state)
+    });
+
+    let mut next = DfaNextElems::new();
+    let mut finals: HashSet<State> = HashSet::new();
+    for (state, _) in dfa.next.iter() {
+        if blocks[state] < next.len() {
+            continue;
+        }
+
+        let (is_final, by_block) = signature(dfa, &blocks, state);
+        if is_final {
+            finals.insert(blocks[state].into());
+        }
+        next.push(
+            by_block
+                .into_iter()
+                .map(|(block, set)| (set, block.into()))
+                .collect::<DfaNextElem>(),
+        );
+    }
+
+    SymbolicDFA {
+        next,
+        initial: blocks[dfa.initial].into(),
+        finals,
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::{State, Symbol, DFA, NFA};
+
+use nicole::{typedvec::TypedVec, IdLike};
+
+mod convert;
+mod minimize;
+mod reverse;
+mod symbol_set;
+
+pub use self::symbol_set::SymbolSet;
+
+pub type NfaNextElem = Vec<(SymbolSet, BTreeSet<State>)>;
+pub type NfaNextElems = TypedVec<State, NfaNextElem>;
+pub type EpsilonElems = TypedVec<State, BTreeSet<State>>;
+pub type DfaNextElem = Vec<(SymbolSet, State)>;
+pub type DfaNextElems = TypedVec<State, DfaNextElem>;
+
+#[derive(Clone, Debug)]
+pub struct SymbolicNFA {
+    pub next: NfaNextElems,
+    pub epsilon: EpsilonElems,
+    pub initials: HashSet<State>,
+    pub finals: HashSet<State>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SymbolicDFA {
+    pub next: DfaNextElems,
+    pub initial: State,
+    pub finals: HashSet<State>,
+}
+
+impl From<NFA> for SymbolicNFA {
+    fn from(nfa: NFA) -> Self {
+        let mut next = NfaNextElems::new();
+        let mut epsilon = EpsilonElems::new();
+
+        for (_, dict) in nfa.next.iter() {
+            let mut grouped: HashMap<BTreeSet<State>, Vec<Symbol>> = HashMap::new();
+            for (&symbol, next_states) in dict.iter() {
+                if !symbol.is_null() {
+                    grouped.entry(next_states.clone()).or_default().push(symbol);
+                }
+            }
+
+            let mut row: NfaNextElem = grouped
+                .into_iter()
+                .map(|(next_states, symbols)| (symbols.into_iter().collect(), next_states))
+                .collect();
+            row.sort();
+
+            next.push(row);
+            epsilon.push(dict.get(&Symbol::null()).cloned().unwrap_or_default());
+        }
+
+        Self {
+            next,
+            epsilon,
+            initials: nfa.initials,
+            finals: nfa.finals,
+        }
+    }
+}
+
+impl From<SymbolicNFA> for SymbolicDFA {
+    fn from(nfa: SymbolicNFA) -> Self {
+        convert::determinize(&nfa)
+    }
+}
+
+impl From<DFA> for SymbolicDFA {
+    fn from(dfa: DFA) -> Self {
+        let mut next = DfaNextElems::new();
+
+        for (_, dict) in dfa.next.iter() {
+            let mut grouped: HashMap<State, Vec<Symbol>> = HashMap::new();
+            for (&symbol, &next_state) in dict.iter() {
+                grouped.entry(next_state).or_default().push(symbol);
+            }
+
+            let mut row: DfaNextElem = grouped
+                .into_iter()
+                .map(|(next_state, symbols)| (symbols.into_iter().collect(), next_state))
+                .collect();
+            row.sort();
+
+            next.push(row);
+        }
+
+        Self {
+            next,
+            initial: dfa.initial,
+            finals: dfa.finals,
+        }
+    }
+}
+
+impl SymbolicNFA {
+    // Adds the given states and everything reachable from them by epsilon transitions, so only
+    // the closures of states actually reached are computed.
+    fn close(&self, set: &mut BTreeSet<State>, states: impl IntoIterator<Item = State>) {
+        let mut stack: Vec<State> = states.into_iter().collect();
+        while let Some(state) = stack.pop() {
+            if set.insert(state) {
+                stack.extend(self.epsilon[state].iter());
+            }
+        }
+    }
+
+    pub fn matches(&self, string: &[Symbol]) -> bool {
+        let mut current: BTreeSet<State> = BTreeSet::new();
+        self.close(&mut current, self.initials.iter().copied());
+
+        for &symbol in string {
+            let mut next: BTreeSet<State> = BTreeSet::new();
+            self.close(
+                &mut next,
+                current
+                    .iter()
+                    .flat_map(|&state| self.next[state].iter())
+                    .filter(|(set, _)| set.contains(symbol))
+                    .flat_map(|(_, next_states)| next_states.iter().copied()),
+            );
+            if next.is_empty() {
+                return false;
+            }
+            current = next;
+        }
+
+        current.iter().any(|x| self.finals.contains(x))
+    }
+}
+
+impl SymbolicDFA {
+    pub fn minimize(self) -> Self {
+        minimize::minimize(&self)
+    }
+
+    pub fn matches(&self, string: &[Symbol]) -> bool {
+        let mut state = self.initial;
+
+        for &symbol in string {
+            match self.next[state]
+                .iter()
+                .find(|(set, _)| set.contains(symbol))
+            {
+                Some(&(_, next_state)) => state = next_state,
+                None => return false,
+            }
+        }
+
+        self.finals.contains(&state)
+    }
+
+    pub fn reverse(&self) -> SymbolicNFA {
+        reverse::reverse(self)
+    }
+}
@@ -0,0 +1,23 @@
+use std::{collections::BTreeSet, iter};
+
+use super::{EpsilonElems, NfaNextElems, SymbolicDFA, SymbolicNFA};
+
+pub fn reverse(dfa: &SymbolicDFA) -> SymbolicNFA {
+    let mut prev = NfaNextElems::new();
+    prev.resize_with(dfa.next.len(), Vec::new);
+    let mut epsilon = EpsilonElems::new();
+    epsilon.resize_with(dfa.next.len(), BTreeSet::new);
+
+    for (state, row) in dfa.next.iter() {
+        for (set, next_state) in row.iter() {
+            prev[*next_state].push((set.clone(), iter::once(state).collect()));
+        }
+    }
+
+    SymbolicNFA {
+        next: prev,
+        epsilon,
+        initials: dfa.finals.clone(),
+        finals: iter::once(dfa.initial).collect(),
+    }
+}
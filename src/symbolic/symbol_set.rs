@@ -0,0 +1,131 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    iter::FromIterator,
+};
+
+use crate::Symbol;
+
+// A set of symbols, stored as sorted, disjoint and non-adjacent inclusive intervals.
+#[derive(Clone, Debug, Default, Hash, Eq, Ord, PartialEq, PartialOrd)]
+pub struct SymbolSet(Vec<(Symbol, Symbol)>);
+
+impl SymbolSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn single(symbol: Symbol) -> Self {
+        Self(vec![(symbol, symbol)])
+    }
+
+    pub fn range(start: Symbol, end: Symbol) -> Self {
+        Self::from_ranges([(start, end)])
+    }
+
+    pub fn from_ranges(ranges: impl IntoIterator<Item = (Symbol, Symbol)>) -> Self {
+        let mut sorted: Vec<(Symbol, Symbol)> =
+            ranges.into_iter().filter(|(s, e)| s <= e).collect();
+        sorted.sort_unstable();
+
+        let mut ret: Vec<(Symbol, Symbol)> = Vec::with_capacity(sorted.len());
+        for (start, end) in sorted {
+            match ret.last_mut() {
+                Some(last) if i64::from(start.0) <= i64::from(last.1 .0) + 1 => {
+                    last.1 = last.1.max(end);
+                }
+                _ => ret.push((start, end)),
+            }
+        }
+
+        Self(ret)
+    }
+
+    pub fn ranges(&self) -> &[(Symbol, Symbol)] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, symbol: Symbol) -> bool {
+        let idx = self.0.partition_point(|&(_, end)| end < symbol);
+        self.0.get(idx).is_some_and(|&(start, _)| start <= symbol)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_ranges(self.0.iter().chain(other.0.iter()).copied())
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ret = Vec::new();
+        let (mut i, mut j) = (0, 0);
+
+        while i < self.0.len() && j < other.0.len() {
+            let (s1, e1) = self.0[i];
+            let (s2, e2) = other.0[j];
+            let (start, end) = (s1.max(s2), e1.min(e2));
+            if start <= end {
+                ret.push((start, end));
+            }
+            if e1 < e2 {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        Self(ret)
+    }
+
+    // Partitions the union of `sets` into minterms: maximal sets of symbols that belong to exactly the
+    // same members of `sets`. Each minterm is returned along with the indices of the sets containing it.
+    pub fn minterms(sets: &[&SymbolSet]) -> Vec<(SymbolSet, Vec<usize>)> {
+        let mut bounds: Vec<i64> = sets
+            .iter()
+            .flat_map(|set| set.0.iter())
+            .flat_map(|&(start, end)| [i64::from(start.0), i64::from(end.0) + 1])
+            .collect();
+        bounds.sort_unstable();
+        bounds.dedup();
+
+        let mut signatures: BTreeMap<Vec<usize>, Vec<(Symbol, Symbol)>> = BTreeMap::new();
+        for window in bounds.windows(2) {
+            let (start, end) = (Symbol(window[0] as i32), Symbol((window[1] - 1) as i32));
+            let members: Vec<usize> = (0..sets.len())
+                .filter(|&i| sets[i].contains(start))
+                .collect();
+            if !members.is_empty() {
+                signatures.entry(members).or_default().push((start, end));
+            }
+        }
+
+        signatures
+            .into_iter()
+            .map(|(members, ranges)| (SymbolSet::from_ranges(ranges), members))
+            .collect()
+    }
+}
+
+impl FromIterator<Symbol> for SymbolSet {
+    fn from_iter<I: IntoIterator<Item = Symbol>>(iter: I) -> Self {
+        Self::from_ranges(iter.into_iter().map(|s| (s, s)))
+    }
+}
+
+impl Display for SymbolSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, &(start, end)) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            if start == end {
+                write!(f, "{}", start)?;
+            } else {
+                write!(f, "{}-{}", start, end)?;
+            }
+        }
+        Ok(())
+    }
+}
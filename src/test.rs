@@ -1,9 +1,24 @@
-use std::collections::HashSet;
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt,
+};
 
 use proptest::prelude::*;
+use rand::Rng;
 use regex_generate::{Generator, DEFAULT_MAX_REPEAT};
 
-use crate::{Symbol, DFA, NFA};
+use crate::{
+    cfl::{cfl_reachability, cfl_reachability_with_dfa, Grammar, Nonterminal},
+    dfa::{BinaryLayout, StepResult},
+    dot::RankDir,
+    graph::{
+        k_shortest_paths, multi_source_rpq, rpq, rpq_paths, rpq_with_semantics, shortest_path,
+        shortest_path_astar, IncrementalRpq, PathSemantics,
+    },
+    BitNFA, DenseDFA, DfaRef, DotOptions, Finder, Fst, LazyDFA, LexError, Lexer, LimitError,
+    Limits, LiveDFA, LiveNFA, MatchKind, Mealy, Moore, MultiDFA, State, Symbol, SymbolSet,
+    SymbolTable, SymbolicDFA, SymbolicNFA, Token, DFA, NFA,
+};
 
 #[derive(Clone, Debug)]
 pub enum Ast {
@@ -15,8 +30,8 @@ pub enum Ast {
     Range(Box<Ast>, i32, i32),
 }
 
-impl Ast {
-    pub fn to_string(&self) -> String {
+impl fmt::Display for Ast {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fn to_string_rec(ast: &Ast, acc: &mut String) {
             match ast {
                 Ast::Terminal(x) => acc.push_str(x),
@@ -49,7 +64,7 @@ impl Ast {
 
         let mut ret = String::new();
         to_string_rec(self, &mut ret);
-        ret
+        f.write_str(&ret)
     }
 }
 
@@ -90,7 +105,7 @@ proptest! {
 
         for input in inputs {
             println!("{regexp} {input} ::> {dfa:?}");
-            prop_assert!(dfa.matches(&input.bytes().map(|b| Symbol::from_u8(b)).collect::<Vec<_>>()));
+            prop_assert!(dfa.matches(&input.bytes().map(Symbol::from_u8).collect::<Vec<_>>()));
         }
     }
 
@@ -110,7 +125,7 @@ proptest! {
 
         for input in inputs {
             println!("{regexp} {input} ::> {dfa:?}");
-            prop_assert!(dfa.matches(&input.bytes().map(|b| Symbol::from_u8(b)).collect::<Vec<_>>()));
+            prop_assert!(dfa.matches(&input.bytes().map(Symbol::from_u8).collect::<Vec<_>>()));
         }
     }
 
@@ -130,7 +145,7 @@ proptest! {
         let rev = DFA::from(dfa.reverse()).minimize();
 
         for input in inputs {
-            let rev_input = input.bytes().rev().map(|b| Symbol::from_u8(b)).collect::<Vec<_>>();
+            let rev_input = input.bytes().rev().map(Symbol::from_u8).collect::<Vec<_>>();
             println!("{regexp} reversed {rev_input:?} ::> {rev:?}");
             prop_assert!(rev.matches(&rev_input));
         }
@@ -187,3 +202,1193 @@ proptest! {
         prop_assert!(generated.len() >= inputs.len());
     }
 }
+
+#[test]
+fn byte_regex_escapes() {
+    let dfa = DFA::from(NFA::from_byte_regex(r"\x41\n\u{1F600}(é|\.)*").unwrap()).minimize();
+
+    assert!(dfa.matches_str("A\n😀"));
+    assert!(dfa.matches_str("A\n😀é.é"));
+    assert!(!dfa.matches_str("A\n😀e"));
+    assert!(!dfa.matches_bytes(&"A\n😀é".as_bytes()[..5]));
+}
+
+proptest! {
+    #[test]
+    fn byte_regex_literal(s in "\\PC{1,8}") {
+        let dfa = DFA::from(NFA::from_byte_regex(&regex::escape(&s)).map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?).minimize();
+
+        prop_assert!(dfa.matches_str(&s));
+        let longer = format!("{s}x");
+        prop_assert!(!dfa.matches_str(&longer));
+    }
+
+    #[test]
+    fn byte_regex_unicode_class(c in any::<char>()) {
+        let dfa = DFA::from(NFA::from_byte_regex(r"[a-c\u{80}-\u{7FF}\u{E000}-\u{10FFFF}]").unwrap()).minimize();
+        let negated = DFA::from(NFA::from_byte_regex(r"[^a-c\u{80}-\u{7FF}\u{E000}-\u{10FFFF}]").unwrap()).minimize();
+        let expected = ('a'..='c').contains(&c) || ('\u{80}'..='\u{7FF}').contains(&c) || c >= '\u{E000}';
+
+        let s = c.to_string();
+        prop_assert_eq!(dfa.matches_str(&s), expected);
+        prop_assert_eq!(negated.matches_str(&s), !expected);
+    }
+}
+
+proptest! {
+    #[test]
+    fn symbolic_dfa_agrees(ast in arb_ast(), inputs in prop::collection::vec("[a-e]{0,8}", 20)) {
+        let nfa = ast.to_string().parse::<NFA>().map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?;
+        let dfa = DFA::from(nfa.clone());
+        let symbolic_nfa = SymbolicNFA::from(nfa);
+        let symbolic = SymbolicDFA::from(symbolic_nfa.clone());
+        let minimized = symbolic.clone().minimize();
+        let from_dfa = SymbolicDFA::from(dfa.clone()).minimize();
+        let rev_nfa = minimized.reverse();
+        let rev = SymbolicDFA::from(rev_nfa.clone());
+
+        for input in inputs {
+            let symbols = input.bytes().map(Symbol::from_u8).collect::<Vec<_>>();
+            let rev_symbols = symbols.iter().rev().copied().collect::<Vec<_>>();
+            let expected = dfa.matches(&symbols);
+
+            prop_assert_eq!(symbolic_nfa.matches(&symbols), expected);
+            prop_assert_eq!(symbolic.matches(&symbols), expected);
+            prop_assert_eq!(minimized.matches(&symbols), expected);
+            prop_assert_eq!(from_dfa.matches(&symbols), expected);
+            prop_assert_eq!(rev_nfa.matches(&rev_symbols), expected);
+            prop_assert_eq!(rev.matches(&rev_symbols), expected);
+        }
+    }
+}
+
+#[test]
+fn symbolic_minterms() {
+    let a = SymbolSet::range(Symbol(0), Symbol(9));
+    let b = SymbolSet::from_ranges([(Symbol(5), Symbol(14)), (Symbol(20), Symbol(20))]);
+    let minterms = SymbolSet::minterms(&[&a, &b]);
+
+    assert_eq!(
+        minterms,
+        vec![
+            (SymbolSet::range(Symbol(0), Symbol(4)), vec![0]),
+            (SymbolSet::range(Symbol(5), Symbol(9)), vec![0, 1]),
+            (
+                SymbolSet::from_ranges([(Symbol(10), Symbol(14)), (Symbol(20), Symbol(20))]),
+                vec![1]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn rpq_reachability() {
+    let (a, b, c) = (
+        Symbol::from_u8(b'a'),
+        Symbol::from_u8(b'b'),
+        Symbol::from_u8(b'c'),
+    );
+    let graph: Vec<Vec<(Symbol, usize)>> = vec![
+        vec![(a, 1), (c, 3)],
+        vec![(b, 2), (a, 1)],
+        vec![(b, 0)],
+        vec![],
+    ];
+    let dfa = DFA::from("a*b".parse::<NFA>().unwrap()).minimize();
+
+    let pairs = rpq(&graph, &dfa, [0, 1, 2, 3]);
+    let expected: HashSet<(usize, usize)> = [(0, 2), (1, 2), (2, 0)].into_iter().collect();
+    assert_eq!(pairs, expected);
+}
+
+#[test]
+fn constrained_shortest_paths() {
+    let (a, b) = (Symbol::from_u8(b'a'), Symbol::from_u8(b'b'));
+    let graph: Vec<Vec<(Symbol, usize, u32)>> = vec![
+        vec![(a, 1, 1), (b, 2, 1), (a, 2, 5)],
+        vec![(b, 3, 1), (a, 2, 1)],
+        vec![(b, 3, 1), (a, 0, 1)],
+        vec![],
+    ];
+    let dfa = DFA::from("a*b".parse::<NFA>().unwrap()).minimize();
+
+    let path = shortest_path(&graph, &dfa, 0, 3).unwrap();
+    assert_eq!(path.cost, 2);
+    assert_eq!(path.vertices, vec![0, 1, 3]);
+    assert_eq!(path.symbols, vec![a, b]);
+    assert_eq!(path.states.len(), 3);
+    assert_eq!(Some(path), shortest_path_astar(&graph, &dfa, 0, 3, |_| 0));
+
+    let paths = k_shortest_paths(&graph, &dfa, 0, 3, 4);
+    let costs: Vec<u32> = paths.iter().map(|p| p.cost).collect();
+    assert_eq!(costs, vec![2, 3, 5, 6]);
+    assert!(paths.iter().all(|p| dfa.matches(&p.symbols)));
+
+    assert_eq!(shortest_path(&graph, &dfa, 3, 0), None);
+}
+
+fn arb_graph() -> impl Strategy<Value = Vec<Vec<(Symbol, usize)>>> {
+    (1..12usize).prop_flat_map(|n| {
+        prop::collection::vec(
+            prop::collection::vec(((0..5).prop_map(Symbol), 0..n), 0..4),
+            n,
+        )
+    })
+}
+
+proptest! {
+    #[test]
+    fn multi_source_rpq_agrees(ast in arb_ast(), graph in arb_graph()) {
+        let dfa = DFA::from(ast.to_string().parse::<NFA>().map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?).minimize();
+        let sources = 0..graph.len();
+
+        let expected = rpq(&graph, &dfa, sources.clone());
+        let reachability = multi_source_rpq(&graph, &dfa, sources.clone());
+        prop_assert_eq!(reachability.pairs(), expected);
+        prop_assert!(reachability.iter().all(|(_, _, state)| dfa.finals.contains(&state)));
+
+        #[cfg(feature = "rayon")]
+        prop_assert_eq!(crate::graph::par_multi_source_rpq(&graph, &dfa, sources), reachability);
+    }
+}
+
+#[test]
+fn rpq_path_semantics() {
+    let (a, b) = (Symbol::from_u8(b'a'), Symbol::from_u8(b'b'));
+    let graph: Vec<Vec<(Symbol, usize)>> = vec![vec![(a, 1)], vec![(a, 1), (a, 0), (b, 2)], vec![]];
+    let dfa = DFA::from("a*b".parse::<NFA>().unwrap()).minimize();
+
+    let simple: Vec<_> = rpq_paths(&graph, &dfa, 0, PathSemantics::Simple).collect();
+    assert_eq!(simple.len(), 1);
+    assert_eq!(simple[0].vertices, vec![0, 1, 2]);
+
+    let trails: Vec<_> = rpq_paths(&graph, &dfa, 0, PathSemantics::Trail).collect();
+    assert_eq!(trails.len(), 2);
+    assert!(trails.iter().any(|p| p.vertices == vec![0, 1, 1, 2]));
+
+    let walks: Vec<_> = rpq_paths(&graph, &dfa, 0, PathSemantics::Arbitrary)
+        .take(3)
+        .map(|p| p.symbols.len())
+        .collect();
+    assert_eq!(walks, vec![2, 3, 4]);
+
+    let expected: HashSet<(usize, usize)> = [(0, 2), (1, 2)].into_iter().collect();
+    for semantics in [
+        PathSemantics::Arbitrary,
+        PathSemantics::Simple,
+        PathSemantics::Trail,
+    ] {
+        assert_eq!(
+            rpq_with_semantics(&graph, &dfa, [0, 1, 2], semantics),
+            expected
+        );
+    }
+}
+
+#[test]
+fn cfl_balanced_reachability() {
+    let (a, b) = (Symbol::from_u8(b'a'), Symbol::from_u8(b'b'));
+    let (s, t, open, close) = (
+        Nonterminal::from(0),
+        Nonterminal::from(1),
+        Nonterminal::from(2),
+        Nonterminal::from(3),
+    );
+    let mut grammar = Grammar::new();
+    grammar
+        .add_epsilon(s)
+        .add_binary(s, s, s)
+        .add_binary(s, open, t)
+        .add_binary(t, s, close)
+        .add_terminal(open, a)
+        .add_terminal(close, b);
+
+    let word = b"aabbab";
+    let graph: Vec<Vec<(Symbol, usize)>> = (0..=word.len())
+        .map(|i| {
+            word.get(i)
+                .map(|&c| (Symbol::from_u8(c), i + 1))
+                .into_iter()
+                .collect()
+        })
+        .collect();
+
+    let balanced = cfl_reachability(&graph, 0..graph.len(), &grammar);
+    assert!(balanced.contains(0, s, 4));
+    assert!(balanced.contains(1, s, 3));
+    assert!(balanced.contains(0, s, 6));
+    assert!(!balanced.contains(0, s, 5));
+
+    let dfa = DFA::from("a*b*".parse::<NFA>().unwrap()).minimize();
+    let constrained = cfl_reachability_with_dfa(&graph, 0..graph.len(), &grammar, &dfa);
+    assert!(constrained.contains(0, s, 4));
+    assert!(constrained.contains(4, s, 6));
+    assert!(!constrained.contains(0, s, 6));
+    assert!(constrained
+        .pairs(s)
+        .iter()
+        .all(|&(u, v)| balanced.contains(u, s, v)));
+}
+
+proptest! {
+    #[test]
+    fn incremental_rpq_agrees(ast in arb_ast(), edges in prop::collection::vec((0..8usize, (0..5).prop_map(Symbol), 0..8usize), 0..30)) {
+        let dfa = DFA::from(ast.to_string().parse::<NFA>().map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?).minimize();
+        let mut index = IncrementalRpq::new(dfa.clone(), 0..8);
+        let mut current = index.pairs();
+
+        for &(from, symbol, to) in edges.iter() {
+            let added: HashSet<(usize, usize)> = index.insert_edge(from, symbol, to).into_iter().collect();
+            let expected = rpq(index.graph(), &dfa, 0..8);
+            prop_assert_eq!(&index.pairs(), &expected);
+            prop_assert_eq!(added, expected.difference(&current).copied().collect::<HashSet<_>>());
+            current = expected;
+        }
+
+        for &(from, symbol, to) in edges.iter().step_by(2) {
+            let removed: HashSet<(usize, usize)> = index.remove_edge(from, symbol, to).into_iter().collect();
+            let expected = rpq(index.graph(), &dfa, 0..8);
+            prop_assert_eq!(&index.pairs(), &expected);
+            prop_assert_eq!(removed, current.difference(&expected).copied().collect::<HashSet<_>>());
+            current = expected;
+        }
+    }
+}
+
+#[test]
+fn write_dot_options() {
+    let dfa = DFA::from("(a|b)c".parse::<NFA>().unwrap()).minimize();
+    let path = [dfa.initial, dfa.next[dfa.initial][&Symbol::from_u8(b'a')]];
+
+    let mut buffer = Vec::new();
+    let options = DotOptions {
+        rankdir: RankDir::TB,
+        merge_edges: true,
+        state_labels: [(dfa.initial, "start \"here\"".to_string())]
+            .into_iter()
+            .collect(),
+        ..Default::default()
+    }
+    .highlight_path(&path);
+    dfa.write_dot(&mut buffer, &options).unwrap();
+    let dot = String::from_utf8(buffer).unwrap();
+
+    assert!(dot.contains("rankdir=TB;"));
+    assert!(dot.contains(&format!("__start -> {};", dfa.initial)));
+    assert!(dot.contains(&format!(
+        "{} -> {} [ label=\"a,b\", color = red, penwidth = 2 ];",
+        path[0], path[1]
+    )));
+    assert!(dot.contains(&format!(
+        "{} [ label=\"start \\\"here\\\"\", color = red, penwidth = 2 ];",
+        dfa.initial
+    )));
+
+    let mut buffer = Vec::new();
+    dfa.write_dot(
+        &mut buffer,
+        &DotOptions {
+            show_initial: false,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let dot = String::from_utf8(buffer).unwrap();
+
+    assert!(!dot.contains("__start"));
+    assert_eq!(dot.matches("label=\"a\"").count(), 1);
+    assert_eq!(dot.matches("label=\"b\"").count(), 1);
+}
+
+#[test]
+fn reverse_graphviz_marks_initials_and_finals() {
+    let dfa = DFA::from("a(b|cd*)".parse::<NFA>().unwrap()).minimize();
+    let rev = dfa.reverse();
+    assert!(rev.initials.len() > 1);
+
+    let mut buffer = Vec::new();
+    rev.write_dot(&mut buffer, &DotOptions::default()).unwrap();
+    let dot = String::from_utf8(buffer).unwrap();
+
+    for initial in rev.initials.iter() {
+        assert!(dot.contains(&format!("__start -> {};", initial)));
+    }
+    assert_eq!(dot.matches("doublecircle").count(), 1);
+    assert!(dot.contains(&format!("node [shape = doublecircle]; {};", dfa.initial)));
+}
+
+proptest! {
+    #[test]
+    fn dot_round_trip(ast in arb_ast(), inputs in prop::collection::vec("[a-e]{0,8}", 20)) {
+        let nfa = ast.to_string().parse::<NFA>().map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?;
+        let dfa = DFA::from(nfa.clone()).minimize();
+
+        let mut buffer = Vec::new();
+        nfa.write_dot(&mut buffer, &DotOptions::default()).unwrap();
+        let parsed_nfa = NFA::from_dot(std::str::from_utf8(&buffer).unwrap()).map_err(|e| TestCaseError::fail(e.to_string()))?;
+        prop_assert_eq!(&parsed_nfa.next, &nfa.next);
+        prop_assert_eq!(&parsed_nfa.initials, &nfa.initials);
+        prop_assert_eq!(&parsed_nfa.finals, &nfa.finals);
+
+        let mut buffer = Vec::new();
+        dfa.write_dot(&mut buffer, &DotOptions { merge_edges: true, ..Default::default() }).unwrap();
+        let parsed_dfa = DFA::from_dot(std::str::from_utf8(&buffer).unwrap()).map_err(|e| TestCaseError::fail(e.to_string()))?;
+        prop_assert_eq!(&parsed_dfa.next, &dfa.next);
+        prop_assert_eq!(parsed_dfa.initial, dfa.initial);
+        prop_assert_eq!(&parsed_dfa.finals, &dfa.finals);
+
+        for input in inputs {
+            let symbols = input.bytes().map(Symbol::from_u8).collect::<Vec<_>>();
+            prop_assert_eq!(parsed_dfa.matches(&symbols), dfa.matches(&symbols));
+        }
+    }
+}
+
+#[test]
+fn dfa_from_hand_written_dot() {
+    let dot = r#"
+        digraph {
+            node [shape = doublecircle]; q1;
+            node [shape = circle];
+            start [ shape = point ];
+            start -> q0;
+            q0 -> q1 [ label = a ];
+            q1 -> q1 [ label = "a,b" ];
+        }
+    "#;
+    let dfa = DFA::from_dot(dot).unwrap();
+
+    assert!(dfa.matches(&[Symbol::from_u8(b'a'), Symbol::from_u8(b'b')]));
+    assert!(!dfa.matches(&[Symbol::from_u8(b'b')]));
+    assert!(DFA::from_dot("digraph { q0 -> q1 [ label = a ]; q0 -> q2 [ label = a ]; }").is_err());
+
+    assert!(DFA::from_dot("digraph { q0 -> q900000000000 [label=a]; }").is_err());
+    assert!(NFA::from_dot("digraph { q2147483648; }").is_err());
+    assert!(NFA::from_dot("digraph { q2147483646; }").is_err());
+    assert!(DFA::from_dot("digraph { q0 -> q2 [label=a]; }").is_err());
+}
+
+#[cfg(feature = "serde")]
+proptest! {
+    #[test]
+    fn serde_round_trip(ast in arb_ast()) {
+        let nfa = ast.to_string().parse::<NFA>().map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?;
+        let dfa = DFA::from(nfa.clone()).minimize();
+
+        let parsed_nfa: NFA = serde_json::from_str(&serde_json::to_string(&nfa).unwrap()).unwrap();
+        prop_assert_eq!(&parsed_nfa.next, &nfa.next);
+        prop_assert_eq!(&parsed_nfa.initials, &nfa.initials);
+        prop_assert_eq!(&parsed_nfa.finals, &nfa.finals);
+
+        let parsed_dfa: DFA = serde_json::from_str(&serde_json::to_string(&dfa).unwrap()).unwrap();
+        prop_assert_eq!(&parsed_dfa.next, &dfa.next);
+        prop_assert_eq!(parsed_dfa.initial, dfa.initial);
+        prop_assert_eq!(&parsed_dfa.finals, &dfa.finals);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_schema_and_validation() {
+    use nicole::IdLike;
+
+    let dfa = DFA::from("ab".parse::<NFA>().unwrap()).minimize();
+    assert_eq!(
+        serde_json::to_string(&dfa).unwrap(),
+        r#"{"states":3,"initial":0,"finals":[2],"transitions":[[0,0,1],[1,1,2]]}"#
+    );
+
+    let invalid = [
+        r#"{"states":2,"initial":2,"finals":[],"transitions":[]}"#,
+        r#"{"states":2,"initial":0,"finals":[5],"transitions":[]}"#,
+        r#"{"states":2,"initial":0,"finals":[],"transitions":[[0,0,2]]}"#,
+        r#"{"states":2,"initial":0,"finals":[],"transitions":[[0,-1,1]]}"#,
+        r#"{"states":2,"initial":0,"finals":[],"transitions":[[0,0,1],[0,0,0]]}"#,
+        r#"{"states":5000000000000,"initial":0,"finals":[],"transitions":[]}"#,
+        r#"{"states":2147483647,"initial":0,"finals":[],"transitions":[[0,0,1]]}"#,
+    ];
+    for json in invalid {
+        assert!(serde_json::from_str::<DFA>(json).is_err(), "{json}");
+    }
+
+    let nfa: NFA = serde_json::from_str(
+        r#"{"states":2,"initials":[0,1],"finals":[1],"transitions":[[0,null,1]]}"#,
+    )
+    .unwrap();
+    assert!(nfa.next[State(0)][&Symbol::null()].contains(&State(1)));
+    assert!(serde_json::from_str::<NFA>(
+        r#"{"states":1,"initials":[0],"finals":[],"transitions":[[0,-1,0]]}"#
+    )
+    .is_err());
+    assert!(serde_json::from_str::<NFA>(
+        r#"{"states":5000000000000,"initials":[],"finals":[],"transitions":[]}"#
+    )
+    .is_err());
+    assert!(serde_json::from_str::<NFA>(
+        r#"{"states":2147483647,"initials":[0],"finals":[0],"transitions":[]}"#
+    )
+    .is_err());
+}
+
+proptest! {
+    #[test]
+    fn att_round_trip(ast in arb_ast(), inputs in prop::collection::vec("[a-e]{0,8}", 20)) {
+        let nfa = ast.to_string().parse::<NFA>().map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?;
+        let dfa = DFA::from(nfa.clone()).minimize();
+        let table = SymbolTable::from_symbols((0..26).map(Symbol));
+
+        let mut buffer = Vec::new();
+        nfa.write_att(&mut buffer, None).unwrap();
+        let parsed_nfa = NFA::read_att(&buffer[..], None).map_err(|e| TestCaseError::fail(e.to_string()))?;
+        prop_assert_eq!(&parsed_nfa.next, &nfa.next);
+        prop_assert_eq!(&parsed_nfa.initials, &nfa.initials);
+        prop_assert_eq!(&parsed_nfa.finals, &nfa.finals);
+
+        let mut buffer = Vec::new();
+        dfa.reverse().write_att(&mut buffer, Some(&table)).unwrap();
+        let rev = DFA::from(NFA::read_att(&buffer[..], Some(&table)).map_err(|e| TestCaseError::fail(e.to_string()))?);
+
+        let mut buffer = Vec::new();
+        dfa.write_att(&mut buffer, Some(&table)).unwrap();
+        let parsed_dfa = DFA::read_att(&buffer[..], Some(&table)).map_err(|e| TestCaseError::fail(e.to_string()))?;
+
+        for input in inputs {
+            let symbols = input.bytes().map(Symbol::from_u8).collect::<Vec<_>>();
+            let rev_symbols = symbols.iter().rev().copied().collect::<Vec<_>>();
+            prop_assert_eq!(parsed_dfa.matches(&symbols), dfa.matches(&symbols));
+            prop_assert_eq!(rev.matches(&rev_symbols), dfa.matches(&symbols));
+        }
+    }
+}
+
+#[test]
+fn att_format() {
+    let dfa = DFA::from("ab*".parse::<NFA>().unwrap()).minimize();
+    let table = SymbolTable::from_symbols([Symbol::from_u8(b'a'), Symbol::from_u8(b'b')]);
+
+    let mut buffer = Vec::new();
+    dfa.write_att(&mut buffer, Some(&table)).unwrap();
+    assert_eq!(String::from_utf8(buffer).unwrap(), "0\t1\ta\n1\t1\tb\n1\n");
+
+    let mut buffer = Vec::new();
+    dfa.write_att(&mut buffer, None).unwrap();
+    assert_eq!(String::from_utf8(buffer).unwrap(), "0\t1\t1\n1\t1\t2\n1\n");
+
+    let mut buffer = Vec::new();
+    table.write(&mut buffer).unwrap();
+    assert_eq!(String::from_utf8(buffer).unwrap(), "<eps>\t0\na\t1\nb\t2\n");
+    assert_eq!(
+        SymbolTable::read(&b"<eps>\t0\na\t1\nb\t2\n"[..]).unwrap(),
+        table
+    );
+
+    assert!(DFA::read_att(&b"0 1 0\n1\n"[..], None).is_err());
+    assert!(DFA::read_att(&b"0 1 a 0.5\n1 0.0\n"[..], Some(&table)).is_ok());
+    assert!(DFA::read_att(&b"0 900000000000 1\n"[..], None).is_err());
+
+    // A final start state without edges of its own must still be read back as the start.
+    let dfa = DFA::from_transitions(
+        3,
+        State(0),
+        [State(0)],
+        [(State(1), Symbol::from_u8(b'a'), State(2))],
+    )
+    .unwrap();
+    let mut buffer = Vec::new();
+    dfa.write_att(&mut buffer, None).unwrap();
+    assert_eq!(String::from_utf8(buffer.clone()).unwrap(), "0\n1\t2\t1\n");
+    assert!(DFA::read_att(&buffer[..], None).unwrap().matches(&[]));
+    assert!(NFA::read_att(&b"2147483648\n"[..], None).is_err());
+    assert!(NFA::read_att(&b"2147483646\n"[..], None).is_err());
+    assert!(NFA::read_att(&b"0 1 -2147483648\n1\n"[..], None).is_err());
+    assert!(SymbolTable::read(&b"a\t-2147483648\n"[..]).is_err());
+    assert!(SymbolTable::read(&b"a\t-3\n"[..]).is_err());
+
+    let dfa =
+        DFA::from_transitions(2, State(0), [], [(State(0), Symbol(i32::MAX), State(1))]).unwrap();
+    assert!(dfa.write_att(&mut Vec::new(), None).is_err());
+}
+
+proptest! {
+    #[test]
+    fn binary_round_trip(ast in arb_ast(), inputs in prop::collection::vec("[a-e]{0,8}", 20)) {
+        let dfa = DFA::from(ast.to_string().parse::<NFA>().map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?).minimize();
+
+        for layout in [BinaryLayout::Dense, BinaryLayout::Sparse] {
+            let bytes = dfa.to_bytes(layout);
+            let view = DfaRef::new(&bytes).map_err(|e| TestCaseError::fail(e.to_string()))?;
+
+            let parsed = DFA::from_bytes(&bytes).map_err(|e| TestCaseError::fail(e.to_string()))?;
+            prop_assert_eq!(&parsed.next, &dfa.next);
+            prop_assert_eq!(parsed.initial, dfa.initial);
+            prop_assert_eq!(&parsed.finals, &dfa.finals);
+
+            for input in inputs.iter() {
+                let symbols = input.bytes().map(Symbol::from_u8).collect::<Vec<_>>();
+                prop_assert_eq!(view.matches(&symbols), dfa.matches(&symbols));
+            }
+
+            prop_assert!(DfaRef::new(&bytes[..bytes.len() - 1]).is_err());
+        }
+    }
+}
+
+#[test]
+fn binary_malformed_header() {
+    let header = |layout: u16, states: u32, initial: u32, alphabet: u32, transitions: u32| {
+        let mut ret = b"FSAD".to_vec();
+        ret.extend_from_slice(&1u16.to_le_bytes());
+        ret.extend_from_slice(&layout.to_le_bytes());
+        for x in [states, initial, alphabet, transitions] {
+            ret.extend_from_slice(&x.to_le_bytes());
+        }
+        ret
+    };
+
+    assert!(DfaRef::new(&header(0, 0x8000_0000, 0, 0x8000_0000, 0)).is_err());
+    assert!(DfaRef::new(&header(0, 0x7fff_ffff, 0, 0xffff_ffff, 0)).is_err());
+    assert!(DfaRef::new(&header(1, 1, 0, 0, 0xffff_ffff)).is_err());
+    assert!(DfaRef::new(&header(0, 1, 1, 0, 0)).is_err());
+    assert!(DfaRef::new(&header(2, 1, 0, 0, 0)).is_err());
+
+    // A single non-final state with an empty alphabet.
+    let mut bytes = header(0, 1, 0, 0, 0);
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    assert!(!DfaRef::new(&bytes).unwrap().matches(&[]));
+
+    // A single state with a self-loop on an epsilon alphabet entry.
+    let mut bytes = header(0, 1, 0, 1, 0);
+    bytes.extend_from_slice(&0xffff_ffffu32.to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    assert!(DfaRef::new(&bytes).is_err());
+    assert!(DFA::from_bytes(&bytes).is_err());
+}
+
+proptest! {
+    #[test]
+    fn dense_dfa_agrees(ast in arb_ast(), inputs in prop::collection::vec("[a-f]{0,8}", 20)) {
+        let dfa = DFA::from(ast.to_string().parse::<NFA>().map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?);
+        let dense = DenseDFA::from(&dfa);
+
+        for input in inputs.iter() {
+            let symbols = input.bytes().map(Symbol::from_u8).collect::<Vec<_>>();
+            prop_assert_eq!(dense.run(&symbols), dfa.run(&symbols));
+            prop_assert_eq!(dense.matches(&symbols), dfa.matches(&symbols));
+        }
+    }
+}
+
+#[test]
+fn dense_dfa_classes() {
+    let dfa = DFA::from(NFA::from_byte_regex("[a-y]z|[0-9][0-9]*").unwrap()).minimize();
+    let dense = dfa.to_dense();
+
+    // [a-y], z and [0-9] are the only distinctions, plus the class of unused symbols.
+    assert_eq!(dense.class_count(), 4);
+    assert_eq!(
+        dense.class(Symbol::from_byte(b'a')),
+        dense.class(Symbol::from_byte(b'y'))
+    );
+    assert_ne!(
+        dense.class(Symbol::from_byte(b'y')),
+        dense.class(Symbol::from_byte(b'z'))
+    );
+    assert_eq!(dense.class(Symbol::from_byte(b'!')), 0);
+
+    for s in ["bz", "2023", "", "zz", "a", "1a", "az!"] {
+        assert_eq!(dense.matches_str(s), dfa.matches_str(s), "{}", s);
+    }
+}
+
+proptest! {
+    #[test]
+    fn compressed_alphabet_agrees(ast in arb_ast(), inputs in prop::collection::vec("[a-f]{0,8}", 20)) {
+        let dfa = DFA::from(ast.to_string().parse::<NFA>().map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?).minimize();
+        let (compressed, classes) = dfa.clone().compress_alphabet();
+
+        prop_assert_eq!(&classes.expand(&compressed.next), &dfa.next);
+        for input in inputs.iter() {
+            let symbols = input.bytes().map(Symbol::from_u8).collect::<Vec<_>>();
+            let translated = classes.translate(&symbols);
+            prop_assert_eq!(translated.is_some_and(|x| compressed.matches(&x)), dfa.matches(&symbols));
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn cursor_agrees(ast in arb_ast(), inputs in prop::collection::vec("[a-f]{0,8}", 20)) {
+        let dfa = DFA::from(ast.to_string().parse::<NFA>().map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?).minimize();
+        let live = LiveDFA::new(&dfa);
+        let mut cursor = live.start();
+
+        for input in inputs.iter() {
+            cursor.reset();
+            prop_assert_eq!(cursor.is_accepting(), dfa.matches(&[]));
+
+            let symbols = input.bytes().map(Symbol::from_u8).collect::<Vec<_>>();
+            for i in 1..=symbols.len() {
+                let result = cursor.step(symbols[i - 1]);
+                prop_assert_eq!(cursor.state(), dfa.run(&symbols[..i]));
+                prop_assert_eq!(result == StepResult::Accepting, dfa.matches(&symbols[..i]));
+                prop_assert_eq!(cursor.is_accepting(), dfa.matches(&symbols[..i]));
+            }
+        }
+    }
+}
+
+#[test]
+fn cursor_stays_dead() {
+    let dfa = DFA::from("ab".parse::<NFA>().unwrap());
+    let live = LiveDFA::new(&dfa);
+    let mut cursor = live.start();
+
+    assert_eq!(cursor.step(Symbol::from_u8(b'a')), StepResult::Alive);
+    assert_eq!(cursor.step(Symbol::from_u8(b'b')), StepResult::Accepting);
+    assert_eq!(cursor.step(Symbol::from_u8(b'a')), StepResult::Dead);
+    assert_eq!(cursor.step(Symbol::from_u8(b'b')), StepResult::Dead);
+    assert_eq!(cursor.state(), None);
+
+    cursor.reset();
+    assert_eq!(cursor.state(), Some(dfa.initial));
+    assert_eq!(cursor.step(Symbol::from_u8(b'a')), StepResult::Alive);
+
+    // A complete DFA for "ab" with an explicit sink state q3.
+    let (a, b) = (Symbol::from_u8(b'a'), Symbol::from_u8(b'b'));
+    let dfa = DFA::from_transitions(
+        4,
+        State(0),
+        [State(2)],
+        [
+            (State(0), a, State(1)),
+            (State(0), b, State(3)),
+            (State(1), a, State(3)),
+            (State(1), b, State(2)),
+            (State(2), a, State(3)),
+            (State(2), b, State(3)),
+            (State(3), a, State(3)),
+            (State(3), b, State(3)),
+        ],
+    )
+    .unwrap();
+    let live = LiveDFA::new(&dfa);
+    assert!(!live.is_live(State(3)));
+    let mut cursor = live.start();
+    assert_eq!(cursor.step(b), StepResult::Dead);
+    assert_eq!(cursor.step(a), StepResult::Dead);
+    assert_eq!(cursor.state(), None);
+
+    cursor.reset();
+    assert_eq!(cursor.step(a), StepResult::Alive);
+    assert_eq!(cursor.step(b), StepResult::Accepting);
+    assert_eq!(cursor.step(b), StepResult::Dead);
+}
+
+proptest! {
+    #[test]
+    fn nfa_simulation_agrees(ast in arb_ast(), inputs in prop::collection::vec("[a-f]{0,8}", 20)) {
+        let nfa = ast.to_string().parse::<NFA>().map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?;
+        let dfa = DFA::from(nfa.clone());
+        let bit_nfa = BitNFA::new(&nfa);
+        prop_assert_eq!(bit_nfa.is_some(), nfa.next.len() <= BitNFA::MAX_STATES);
+
+        let live = LiveNFA::new(&nfa);
+        let mut cursor = live.start();
+        for input in inputs.iter() {
+            let symbols = input.bytes().map(Symbol::from_u8).collect::<Vec<_>>();
+            prop_assert_eq!(nfa.matches(&symbols), dfa.matches(&symbols));
+            if let Some(bit_nfa) = bit_nfa.as_ref() {
+                prop_assert_eq!(bit_nfa.matches(&symbols), dfa.matches(&symbols));
+            }
+
+            cursor.reset();
+            for i in 1..=symbols.len() {
+                cursor.step(symbols[i - 1]);
+                prop_assert_eq!(cursor.is_accepting(), dfa.matches(&symbols[..i]));
+            }
+        }
+    }
+}
+
+#[test]
+fn nfa_cursor_dies_without_reachable_finals() {
+    // q2 loops on b forever and can never accept.
+    let (a, b) = (Symbol::from_u8(b'a'), Symbol::from_u8(b'b'));
+    let nfa = NFA::from_transitions(
+        3,
+        [State(0)],
+        [State(1)],
+        [
+            (State(0), a, State(1)),
+            (State(0), b, State(2)),
+            (State(2), b, State(2)),
+        ],
+    )
+    .unwrap();
+    let live = LiveNFA::new(&nfa);
+    assert!(!live.is_live(State(2)));
+
+    let mut cursor = live.start();
+    assert_eq!(cursor.step(b), StepResult::Dead);
+    assert!(cursor.states().is_empty());
+
+    cursor.reset();
+    assert_eq!(cursor.step(a), StepResult::Accepting);
+}
+
+proptest! {
+    #[test]
+    fn lazy_dfa_agrees(ast in arb_ast(), inputs in prop::collection::vec("[a-f]{0,8}", 20), capacity in 1..8usize) {
+        let nfa = ast.to_string().parse::<NFA>().map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?;
+        let dfa = DFA::from(nfa.clone());
+        let mut lazy = LazyDFA::with_capacity(nfa, capacity);
+
+        for input in inputs.iter() {
+            let symbols = input.bytes().map(Symbol::from_u8).collect::<Vec<_>>();
+            prop_assert_eq!(lazy.matches(&symbols), dfa.matches(&symbols));
+            prop_assert!(lazy.cache_len() <= capacity);
+        }
+    }
+}
+
+#[test]
+fn lazy_dfa_bounded_cache() {
+    // The full DFA has over 2^21 states.
+    let nfa = "(a|b)*a(a|b){20,20}".parse::<NFA>().unwrap();
+    let mut lazy = LazyDFA::with_capacity(nfa.clone(), 64);
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..20 {
+        let symbols: Vec<Symbol> = (0..100)
+            .map(|_| Symbol::from_u8(if rng.gen_bool(0.5) { b'a' } else { b'b' }))
+            .collect();
+        assert_eq!(lazy.matches(&symbols), nfa.matches(&symbols));
+        assert!(lazy.cache_len() <= 64);
+    }
+    assert!(lazy.evictions() > 0);
+}
+
+proptest! {
+    #[test]
+    fn regex_state_limit_is_exact(ast in arb_ast()) {
+        let regexp = ast.to_string();
+        let nfa = regexp.parse::<NFA>().map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?;
+        let limits = Limits { max_states: nfa.next.len(), ..Limits::default() };
+
+        prop_assert!(NFA::from_regex_with_limits(&regexp, limits).is_ok());
+        let limits = Limits { max_states: nfa.next.len() - 1, ..limits };
+        let err = NFA::from_regex_with_limits(&regexp, limits).err().unwrap();
+        prop_assert_eq!(err.downcast_ref::<LimitError>(), Some(&LimitError::TooManyStates { limit: limits.max_states }));
+
+        let transitions: usize = nfa.next.iter().flat_map(|(_, dict)| dict.values()).map(|x| x.len()).sum();
+        let limits = Limits { max_transitions: transitions, ..Limits::UNLIMITED };
+        prop_assert!(NFA::from_regex_with_limits(&regexp, limits).is_ok());
+        let limits = Limits { max_transitions: transitions - 1, ..limits };
+        let err = NFA::from_regex_with_limits(&regexp, limits).err().unwrap();
+        prop_assert_eq!(err.downcast_ref::<LimitError>(), Some(&LimitError::TooManyTransitions { limit: limits.max_transitions }));
+    }
+}
+
+#[test]
+fn determinization_limits() {
+    let nfa = "(a|b)*a(a|b){12,12}".parse::<NFA>().unwrap();
+
+    let limits = Limits {
+        max_states: 1000,
+        ..Limits::default()
+    };
+    assert_eq!(
+        DFA::try_from_nfa(&nfa, limits).err(),
+        Some(LimitError::TooManyStates { limit: 1000 })
+    );
+    let limits = Limits {
+        max_transitions: 1000,
+        ..Limits::default()
+    };
+    assert_eq!(
+        DFA::try_from_nfa(&nfa, limits).err(),
+        Some(LimitError::TooManyTransitions { limit: 1000 })
+    );
+
+    let dfa = DFA::try_from_nfa(&nfa, Limits::default()).unwrap();
+    assert_eq!(dfa.next.len(), DFA::from(nfa).next.len());
+
+    let err = NFA::from_regex_with_limits("(a{1000,1000}){1000,1000}", Limits::default())
+        .err()
+        .unwrap();
+    assert!(err.downcast_ref::<LimitError>().is_some());
+
+    // Each copy of the class expands into byte ranges worth over a thousand transitions.
+    let limits = Limits {
+        max_transitions: 10_000,
+        ..Limits::UNLIMITED
+    };
+    let err = NFA::from_byte_regex_with_limits(r"[\u{0}-\u{10FFFF}]{0,100}", limits)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.downcast_ref::<LimitError>(),
+        Some(&LimitError::TooManyTransitions { limit: 10_000 })
+    );
+    let nfa = NFA::from_byte_regex_with_limits(r"[\u{0}-\u{10FFFF}]{0,1}", limits).unwrap();
+    assert!(nfa.matches(
+        &"\u{10FFFF}"
+            .bytes()
+            .map(Symbol::from_byte)
+            .collect::<Vec<_>>()
+    ));
+}
+
+// The match starting at the smallest position at or after `from`, by brute force.
+fn brute_force_find(
+    dfa: &DFA,
+    haystack: &[Symbol],
+    from: usize,
+    kind: MatchKind,
+) -> Option<std::ops::Range<usize>> {
+    (from..=haystack.len()).find_map(|start| {
+        let mut ends = (start..=haystack.len()).filter(|&end| dfa.matches(&haystack[start..end]));
+        match kind {
+            MatchKind::LeftmostLongest => ends.next_back(),
+            MatchKind::LeftmostShortest => ends.next(),
+            MatchKind::LeftmostFirst => unreachable!(),
+        }
+        .map(|end| start..end)
+    })
+}
+
+// Non-overlapping matches, each found by `find` from where the previous one ended.
+fn successive_matches(
+    len: usize,
+    mut find: impl FnMut(usize) -> Option<std::ops::Range<usize>>,
+) -> Vec<std::ops::Range<usize>> {
+    let mut ret = vec![];
+    let mut from = 0;
+    while let Some(range) = (from <= len).then(|| find(from)).flatten() {
+        from = if range.is_empty() {
+            range.end + 1
+        } else {
+            range.end
+        };
+        ret.push(range);
+    }
+    ret
+}
+
+proptest! {
+    #[test]
+    fn find_agrees_with_brute_force(ast in arb_ast(), haystack in "[a-f!]{0,16}") {
+        let regexp = ast.to_string();
+        let nfa = regexp.parse::<NFA>().map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?;
+        let dfa = DFA::from(nfa.clone()).minimize();
+        // '!' is outside the alphabet of every test regex.
+        let symbols = haystack
+            .bytes()
+            .map(|b| if b == b'!' { Symbol::from_byte(b) } else { Symbol::from_u8(b) })
+            .collect::<Vec<_>>();
+
+        for kind in [MatchKind::LeftmostLongest, MatchKind::LeftmostShortest] {
+            let expected = successive_matches(symbols.len(), |from| brute_force_find(&dfa, &symbols, from, kind));
+            for finder in [Finder::new(&dfa, kind), Finder::from_nfa(&nfa, kind)] {
+                prop_assert_eq!(finder.find(&symbols), expected.first().cloned());
+                prop_assert_eq!(&finder.find_iter(&symbols).collect::<Vec<_>>(), &expected);
+            }
+        }
+
+        let re = regex::Regex::new(&regexp).map_err(|e| TestCaseError::fail(e.to_string()))?;
+        let expected = successive_matches(symbols.len(), |from| re.find_at(&haystack, from).map(|m| m.range()));
+        let finder = Finder::from_nfa(&nfa, MatchKind::LeftmostFirst);
+        prop_assert_eq!(finder.find_iter(&symbols).collect::<Vec<_>>(), expected);
+
+        let expected: Vec<_> = (0..=symbols.len())
+            .flat_map(|start| (start..=symbols.len()).map(move |end| start..end))
+            .filter(|range| dfa.matches(&symbols[range.clone()]))
+            .collect();
+        for finder in [Finder::new(&dfa, MatchKind::LeftmostLongest), Finder::from_nfa(&nfa, MatchKind::LeftmostFirst)] {
+            prop_assert_eq!(&finder.find_overlapping(&symbols).collect::<Vec<_>>(), &expected);
+        }
+    }
+}
+
+#[test]
+fn leftmost_first_prefers_earlier_alternatives() {
+    let nfa = "a|ab".parse::<NFA>().unwrap();
+    let haystack = [Symbol::from_u8(b'a'), Symbol::from_u8(b'b')];
+
+    assert_eq!(
+        Finder::from_nfa(&nfa, MatchKind::LeftmostFirst).find(&haystack),
+        Some(0..1)
+    );
+    assert_eq!(
+        Finder::from_nfa(&nfa, MatchKind::LeftmostLongest).find(&haystack),
+        Some(0..2)
+    );
+}
+
+proptest! {
+    #[test]
+    fn multi_dfa_tags(asts in prop::collection::vec(arb_ast(), 1..4), inputs in prop::collection::vec("[a-f]{0,8}", 20)) {
+        let regexes: Vec<String> = asts.iter().map(|ast| ast.to_string()).collect();
+        let dfas = regexes
+            .iter()
+            .map(|regex| regex.parse::<NFA>().map(DFA::from))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?;
+        let multi = MultiDFA::from_regexes(regexes.iter().map(String::as_str)).map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?;
+        let minimized = multi.clone().minimize();
+        prop_assert!(minimized.next.len() <= multi.next.len());
+
+        for input in inputs.iter() {
+            let symbols = input.bytes().map(Symbol::from_u8).collect::<Vec<_>>();
+            let expected: BTreeSet<usize> = (0..dfas.len()).filter(|&i| dfas[i].matches(&symbols)).collect();
+            prop_assert_eq!(multi.patterns(&symbols), &expected);
+            prop_assert_eq!(minimized.patterns(&symbols), &expected);
+        }
+    }
+}
+
+#[test]
+fn multi_dfa_minimize_respects_tags() {
+    // The states reached by "ab" and "ac" are both final and equivalent in a plain DFA, but carry
+    // different tags, so minimization must keep them apart.
+    let multi = MultiDFA::from_regexes(["ab|ac", "ac|ab", "ab"])
+        .unwrap()
+        .minimize();
+    assert_eq!(
+        multi.patterns(&[Symbol::from_u8(b'a'), Symbol::from_u8(b'b')]),
+        &[0, 1, 2].into()
+    );
+    assert_eq!(
+        multi.patterns(&[Symbol::from_u8(b'a'), Symbol::from_u8(b'c')]),
+        &[0, 1].into()
+    );
+    assert!(!multi.matches(&[Symbol::from_u8(b'a')]));
+    assert_eq!(multi.next.len(), 4);
+}
+
+#[test]
+fn lexer_maximal_munch_and_priority() {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Kind {
+        If,
+        Ident,
+        Number,
+        Space,
+        Arrow,
+        Minus,
+    }
+
+    let lexer = Lexer::new([
+        ("if", Kind::If),
+        ("[a-z][a-z0-9]*", Kind::Ident),
+        ("[0-9][0-9]*", Kind::Number),
+        ("[ \\n][ \\n]*", Kind::Space),
+        ("->", Kind::Arrow),
+        ("-", Kind::Minus),
+    ])
+    .unwrap();
+
+    let tokens = lexer.tokenize_str("if iffy->-42").unwrap();
+    let expected = [
+        (Kind::If, 0..2),
+        (Kind::Space, 2..3),
+        (Kind::Ident, 3..7),
+        (Kind::Arrow, 7..9),
+        (Kind::Minus, 9..10),
+        (Kind::Number, 10..12),
+    ];
+    assert_eq!(
+        tokens,
+        expected.map(|(kind, span)| Token { kind, span }).to_vec()
+    );
+
+    assert_eq!(lexer.tokenize_str("if x $"), Err(LexError { position: 5 }));
+    assert_eq!(lexer.tokenize_str(""), Ok(vec![]));
+}
+
+// Random complete transitions over symbols 0..3, with outputs in 0..3.
+fn arb_transducer() -> impl Strategy<Value = (usize, Vec<(usize, i32, u8, usize)>)> {
+    (1..8usize).prop_flat_map(|states| {
+        let transitions =
+            prop::collection::vec((0..states, 0..3u8), states * 3).prop_map(move |targets| {
+                targets
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, (next_state, output))| (i / 3, (i % 3) as i32, output, next_state))
+                    .collect()
+            });
+        (Just(states), transitions)
+    })
+}
+
+proptest! {
+    #[test]
+    fn mealy_minimize_preserves_outputs((states, transitions) in arb_transducer(), inputs in prop::collection::vec(prop::collection::vec(0..3i32, 0..10), 20)) {
+        let mealy = Mealy::from_transitions(
+            states,
+            State(0),
+            transitions.iter().map(|&(state, symbol, output, next_state)| (State::from(state), Symbol(symbol), output, State::from(next_state))),
+        ).map_err(TestCaseError::fail)?;
+        let minimized = mealy.clone().minimize();
+        prop_assert!(minimized.next.len() <= states);
+
+        for input in inputs {
+            let input: Vec<Symbol> = input.into_iter().map(Symbol).collect();
+            prop_assert_eq!(minimized.run(&input), mealy.run(&input));
+            prop_assert_eq!(mealy.run(&input).len(), input.len());
+        }
+    }
+
+    #[test]
+    fn moore_minimize_preserves_outputs((states, transitions) in arb_transducer(), inputs in prop::collection::vec(prop::collection::vec(0..3i32, 0..10), 20)) {
+        let outputs: Vec<u8> = transitions.iter().step_by(3).map(|&(_, _, output, _)| output).collect();
+        let moore = Moore::from_transitions(
+            outputs,
+            State(0),
+            transitions.iter().map(|&(state, symbol, _, next_state)| (State::from(state), Symbol(symbol), State::from(next_state))),
+        ).map_err(TestCaseError::fail)?;
+        let minimized = moore.clone().minimize();
+        prop_assert!(minimized.next.len() <= states);
+
+        for input in inputs {
+            let input: Vec<Symbol> = input.into_iter().map(Symbol).collect();
+            prop_assert_eq!(minimized.run(&input), moore.run(&input));
+            prop_assert_eq!(moore.run(&input).len(), input.len() + 1);
+        }
+    }
+}
+
+#[test]
+fn transducer_state_count_is_bounded() {
+    assert!(Mealy::<u8>::from_transitions(usize::MAX, State(0), []).is_err());
+    assert!(Mealy::<u8>::from_transitions(1 << 31, State(0), []).is_err());
+    assert!(Fst::from_transitions(usize::MAX, [], [], []).is_err());
+}
+
+#[test]
+fn transducer_graphviz_labels() {
+    // Outputs 1 after every "b" and 0 otherwise; both states are equivalent.
+    let mealy = Mealy::from_transitions(
+        2,
+        State(0),
+        [
+            (State(0), Symbol(0), 0, State(1)),
+            (State(0), Symbol(1), 1, State(1)),
+            (State(1), Symbol(0), 0, State(0)),
+            (State(1), Symbol(1), 1, State(0)),
+        ],
+    )
+    .unwrap()
+    .minimize();
+    assert_eq!(mealy.next.len(), 1);
+    assert_eq!(mealy.run(&[Symbol(0), Symbol(1), Symbol(1)]), vec![0, 1, 1]);
+
+    let mut out = Vec::new();
+    mealy.write_dot(&mut out, &DotOptions::default()).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("q0 -> q0 [ label=\"a/0\" ];"));
+    assert!(out.contains("q0 -> q0 [ label=\"b/1\" ];"));
+
+    let moore = Moore::from_transitions(
+        vec!["even", "odd"],
+        State(0),
+        [
+            (State(0), Symbol(0), State(1)),
+            (State(1), Symbol(0), State(0)),
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        moore.run(&[Symbol(0); 3]),
+        vec!["even", "odd", "even", "odd"]
+    );
+
+    let mut out = Vec::new();
+    moore.write_dot(&mut out, &DotOptions::default()).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("q1 [ label=\"q1/odd\" ];"));
+    assert!(out.contains("q0 -> q1 [ label=\"a\" ];"));
+}
+
+proptest! {
+    #[test]
+    fn fst_compose_identities(ast1 in arb_ast(), ast2 in arb_ast(), inputs in prop::collection::vec("[a-f]{0,8}", 20)) {
+        let nfa1 = ast1.to_string().parse::<NFA>().map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?;
+        let nfa2 = ast2.to_string().parse::<NFA>().map_err(|_| TestCaseError::fail("Failed to parse NFA".to_string()))?;
+        let (dfa1, dfa2) = (DFA::from(nfa1.clone()), DFA::from(nfa2.clone()));
+
+        // Composing identities intersects their languages.
+        let composed = Fst::from(&nfa1).compose(&Fst::from(&nfa2));
+        let input = DFA::from(composed.input_projection());
+        let output = DFA::from(composed.invert().input_projection());
+        let image = DFA::from(Fst::from(&nfa2).apply_dfa(&dfa1));
+
+        for s in inputs.iter() {
+            let symbols = s.bytes().map(Symbol::from_u8).collect::<Vec<_>>();
+            let expected = dfa1.matches(&symbols) && dfa2.matches(&symbols);
+            prop_assert_eq!(input.matches(&symbols), expected);
+            prop_assert_eq!(output.matches(&symbols), expected);
+            prop_assert_eq!(image.matches(&symbols), expected);
+        }
+    }
+}
+
+#[test]
+fn fst_rewriting() {
+    let (a, b, c) = (
+        Symbol::from_u8(b'a'),
+        Symbol::from_u8(b'b'),
+        Symbol::from_u8(b'c'),
+    );
+    let to_symbols = |s: &str| s.bytes().map(Symbol::from_u8).collect::<Vec<_>>();
+
+    // Rewrites a to b, deletes c and copies b.
+    let rewrite = Fst::from_transitions(
+        1,
+        [State(0)],
+        [State(0)],
+        [
+            (State(0), Some(a), Some(b), State(0)),
+            (State(0), Some(b), Some(b), State(0)),
+            (State(0), Some(c), None, State(0)),
+        ],
+    )
+    .unwrap();
+    // Inserts a c after every b.
+    let insert = Fst::from_transitions(
+        2,
+        [State(0)],
+        [State(0)],
+        [
+            (State(0), Some(b), Some(b), State(1)),
+            (State(1), None, Some(c), State(0)),
+        ],
+    )
+    .unwrap();
+
+    let image = DFA::from(rewrite.apply(&"a(c|a)".parse::<NFA>().unwrap()));
+    for (s, expected) in [("b", true), ("bb", true), ("ab", false), ("bc", false)] {
+        assert_eq!(image.matches(&to_symbols(s)), expected, "{}", s);
+    }
+
+    let image = DFA::from(
+        rewrite
+            .compose(&insert)
+            .apply(&"ac*a".parse::<NFA>().unwrap()),
+    );
+    for (s, expected) in [
+        ("bcbc", true),
+        ("bb", false),
+        ("bcb", false),
+        ("acac", false),
+    ] {
+        assert_eq!(image.matches(&to_symbols(s)), expected, "{}", s);
+    }
+
+    // The inverse maps outputs back to every input they come from.
+    let preimage = DFA::from(rewrite.invert().apply(&"bb".parse::<NFA>().unwrap()));
+    for (s, expected) in [("ab", true), ("cbca", true), ("bcb", true), ("b", false)] {
+        assert_eq!(preimage.matches(&to_symbols(s)), expected, "{}", s);
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use nicole::{typedvec::TypedVec, IdLike};
+
+use crate::{check_state, check_state_count, nfa, State, Symbol, DFA, NFA};
+
+// `None` on either side of a label is epsilon.
+pub type FstLabel = (Option<Symbol>, Option<Symbol>);
+pub type FstNextElem = HashMap<FstLabel, BTreeSet<State>>;
+
+// A nondeterministic finite-state transducer, relating input strings to output strings.
+#[derive(Clone, Debug)]
+pub struct Fst {
+    pub next: TypedVec<State, FstNextElem>,
+    pub initials: HashSet<State>,
+    pub finals: HashSet<State>,
+}
+
+fn to_option(symbol: Symbol) -> Option<Symbol> {
+    (!symbol.is_null()).then_some(symbol)
+}
+
+// The identity transducer on the language of the NFA.
+impl From<&NFA> for Fst {
+    fn from(nfa: &NFA) -> Self {
+        let mut next: TypedVec<State, FstNextElem> = TypedVec::new();
+        *next = nfa
+            .next
+            .iter()
+            .map(|(_, dict)| {
+                dict.iter()
+                    .map(|(&symbol, next_states)| {
+                        let symbol = to_option(symbol);
+                        ((symbol, symbol), next_states.clone())
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            next,
+            initials: nfa.initials.clone(),
+            finals: nfa.finals.clone(),
+        }
+    }
+}
+
+impl From<&DFA> for Fst {
+    fn from(dfa: &DFA) -> Self {
+        let mut next: TypedVec<State, FstNextElem> = TypedVec::new();
+        *next = dfa
+            .next
+            .iter()
+            .map(|(_, dict)| {
+                dict.iter()
+                    .map(|(&symbol, &next_state)| {
+                        ((Some(symbol), Some(symbol)), [next_state].into())
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            next,
+            initials: [dfa.initial].into(),
+            finals: dfa.finals.clone(),
+        }
+    }
+}
+
+impl Fst {
+    pub fn from_transitions(
+        states: usize,
+        initials: impl IntoIterator<Item = State>,
+        finals: impl IntoIterator<Item = State>,
+        transitions: impl IntoIterator<Item = (State, Option<Symbol>, Option<Symbol>, State)>,
+    ) -> Result<Self, String> {
+        let states = check_state_count(states)?;
+        let mut next: TypedVec<State, FstNextElem> = TypedVec::new();
+        next.try_reserve_exact(states)
+            .map_err(|_| format!("cannot allocate {} states", states))?;
+        next.resize_with(states, HashMap::new);
+
+        for (state, input, output, next_state) in transitions {
+            let state = check_state(state, states)?;
+            let next_state = check_state(next_state, states)?;
+            next[state]
+                .entry((input, output))
+                .or_default()
+                .insert(next_state);
+        }
+
+        Ok(Self {
+            next,
+            initials: initials
+                .into_iter()
+                .map(|x| check_state(x, states))
+                .collect::<Result<_, _>>()?,
+            finals: finals
+                .into_iter()
+                .map(|x| check_state(x, states))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn invert(&self) -> Self {
+        let mut next: TypedVec<State, FstNextElem> = TypedVec::new();
+        *next = self
+            .next
+            .iter()
+            .map(|(_, dict)| {
+                dict.iter()
+                    .map(|(&(input, output), next_states)| ((output, input), next_states.clone()))
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            next,
+            initials: self.initials.clone(),
+            finals: self.finals.clone(),
+        }
+    }
+
+    fn project(&self, side: impl Fn(FstLabel) -> Option<Symbol>) -> NFA {
+        let mut next = nfa::NextElems::new();
+        *next = self
+            .next
+            .iter()
+            .map(|(_, dict)| {
+                let mut ret = nfa::NextElem::new();
+                for (&label, next_states) in dict.iter() {
+                    ret.entry(side(label).unwrap_or_else(Symbol::null))
+                        .or_default()
+                        .extend(next_states.iter().copied());
+                }
+                ret
+            })
+            .collect();
+
+        NFA {
+            next,
+            initials: self.initials.clone(),
+            finals: self.finals.clone(),
+        }
+    }
+
+    // The NFA accepting the domain of the relation.
+    pub fn input_projection(&self) -> NFA {
+        self.project(|(input, _)| input)
+    }
+
+    // The NFA accepting the range of the relation.
+    pub fn output_projection(&self) -> NFA {
+        self.project(|(_, output)| output)
+    }
+
+    // Relates x to z whenever `self` relates x to some y and `other` relates y to z. Either side
+    // may move alone on an epsilon in the middle; the duplicate paths this allows are harmless
+    // since transitions carry no weights.
+    pub fn compose(&self, other: &Fst) -> Fst {
+        let mut translate: HashMap<(State, State), State> = HashMap::new();
+        let mut queue: VecDeque<(State, State)> = VecDeque::new();
+        let mut ret = Fst {
+            next: TypedVec::new(),
+            initials: HashSet::new(),
+            finals: HashSet::new(),
+        };
+
+        let mut state_id = |pair: (State, State), ret: &mut Fst, queue: &mut VecDeque<_>| {
+            *translate.entry(pair).or_insert_with(|| {
+                queue.push_back(pair);
+                ret.next.push(FstNextElem::new());
+                State::from(ret.next.len() - 1)
+            })
+        };
+
+        for &x in self.initials.iter() {
+            for &y in other.initials.iter() {
+                let state = state_id((x, y), &mut ret, &mut queue);
+                ret.initials.insert(state);
+            }
+        }
+
+        while let Some((x, y)) = queue.pop_front() {
+            let state = state_id((x, y), &mut ret, &mut queue);
+            if self.finals.contains(&x) && other.finals.contains(&y) {
+                ret.finals.insert(state);
+            }
+
+            let mut edges: Vec<(FstLabel, (State, State))> = Vec::new();
+            for (&(input, middle), next_xs) in self.next[x].iter() {
+                match middle {
+                    None => edges.extend(next_xs.iter().map(|&nx| ((input, None), (nx, y)))),
+                    Some(_) => {
+                        for (&(_, output), next_ys) in other.next[y]
+                            .iter()
+                            .filter(|((other_input, _), _)| *other_input == middle)
+                        {
+                            for &nx in next_xs.iter() {
+                                edges.extend(next_ys.iter().map(|&ny| ((input, output), (nx, ny))));
+                            }
+                        }
+                    }
+                }
+            }
+            for (&(input, output), next_ys) in other.next[y].iter() {
+                if input.is_none() {
+                    edges.extend(next_ys.iter().map(|&ny| ((None, output), (x, ny))));
+                }
+            }
+
+            for (label, pair) in edges {
+                let next_state = state_id(pair, &mut ret, &mut queue);
+                ret.next[state].entry(label).or_default().insert(next_state);
+            }
+        }
+
+        ret
+    }
+
+    // The image of the NFA's language under the relation.
+    pub fn apply(&self, nfa: &NFA) -> NFA {
+        Fst::from(nfa).compose(self).output_projection()
+    }
+
+    pub fn apply_dfa(&self, dfa: &DFA) -> NFA {
+        Fst::from(dfa).compose(self).output_projection()
+    }
+}
@@ -0,0 +1,122 @@
+use std::{collections::HashMap, fmt::Display, hash::Hash, io, iter};
+
+use nicole::typedvec::TypedVec;
+
+use crate::{check_state, check_state_count, dot, refine::refine, DotOptions, State, Symbol};
+
+pub type MealyNextElem<O> = HashMap<Symbol, (State, O)>;
+
+// A deterministic transducer with an output on every transition.
+#[derive(Clone, Debug)]
+pub struct Mealy<O> {
+    pub next: TypedVec<State, MealyNextElem<O>>,
+    pub initial: State,
+}
+
+impl<O: Clone + Eq + Hash> Mealy<O> {
+    pub fn from_transitions(
+        states: usize,
+        initial: State,
+        transitions: impl IntoIterator<Item = (State, Symbol, O, State)>,
+    ) -> Result<Self, String> {
+        let states = check_state_count(states)?;
+        let mut next: TypedVec<State, MealyNextElem<O>> = TypedVec::new();
+        next.try_reserve_exact(states)
+            .map_err(|_| format!("cannot allocate {} states", states))?;
+        next.resize_with(states, HashMap::new);
+
+        for (state, symbol, output, next_state) in transitions {
+            let state = check_state(state, states)?;
+            let next_state = check_state(next_state, states)?;
+            if next[state].insert(symbol, (next_state, output)).is_some() {
+                return Err(format!(
+                    "nondeterministic transition from {} on {}",
+                    state, symbol
+                ));
+            }
+        }
+
+        Ok(Self {
+            next,
+            initial: check_state(initial, states)?,
+        })
+    }
+
+    pub fn step(&self, state: State, symbol: Symbol) -> Option<(State, &O)> {
+        self.next[state]
+            .get(&symbol)
+            .map(|(next_state, output)| (*next_state, output))
+    }
+
+    // Returns one output per symbol read. Reading stops at the first symbol without a transition,
+    // so the output is shorter than the input if the machine gets stuck.
+    pub fn run(&self, input: &[Symbol]) -> Vec<O> {
+        let mut state = self.initial;
+        let mut ret = Vec::new();
+
+        for &symbol in input {
+            let Some((next_state, output)) = self.step(state, symbol) else {
+                break;
+            };
+            ret.push(output.clone());
+            state = next_state;
+        }
+
+        ret
+    }
+
+    // Merges states that produce the same outputs on every input.
+    pub fn minimize(self) -> Self {
+        let signature = |blocks: &TypedVec<State, usize>, state: State| {
+            let mut ret: Vec<(Symbol, &O, usize)> = self.next[state]
+                .iter()
+                .map(|(&symbol, (next_state, output))| (symbol, output, blocks[*next_state]))
+                .collect();
+            ret.sort_by_key(|&(symbol, _, _)| symbol);
+            ret
+        };
+        let blocks = refine(self.next.len(), signature);
+
+        let mut next: TypedVec<State, MealyNextElem<O>> = TypedVec::new();
+        for (state, dict) in self.next.iter() {
+            if blocks[state] < next.len() {
+                continue;
+            }
+            next.push(
+                dict.iter()
+                    .map(|(&symbol, (next_state, output))| {
+                        (symbol, (blocks[*next_state].into(), output.clone()))
+                    })
+                    .collect(),
+            );
+        }
+
+        Self {
+            next,
+            initial: blocks[self.initial].into(),
+        }
+    }
+}
+
+impl<O: Display> Mealy<O> {
+    // Edges are labelled "in/out".
+    pub fn write_dot<W: io::Write>(&self, w: W, options: &DotOptions) -> io::Result<()> {
+        dot::write_dot(
+            w,
+            self.next.len(),
+            iter::once(self.initial),
+            iter::empty(),
+            self.next.iter().flat_map(|(state, dict)| {
+                dict.iter().map(move |(symbol, (next_state, output))| {
+                    (state, format!("{}/{}", symbol, output), *next_state)
+                })
+            }),
+            options,
+        )
+    }
+
+    pub fn print_graphviz(&self) {
+        self.write_dot(io::stdout().lock(), &DotOptions::default())
+            .unwrap();
+    }
+}
@@ -0,0 +1,9 @@
+mod fst;
+mod mealy;
+mod moore;
+
+pub use self::{
+    fst::{Fst, FstLabel, FstNextElem},
+    mealy::Mealy,
+    moore::Moore,
+};
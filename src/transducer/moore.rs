@@ -0,0 +1,131 @@
+use std::{collections::HashMap, fmt::Display, hash::Hash, io, iter};
+
+use nicole::typedvec::TypedVec;
+
+use crate::{
+    check_state, check_state_count, dfa::NextElems, dot, refine::refine, DotOptions, State, Symbol,
+};
+
+// A deterministic transducer with an output on every state.
+#[derive(Clone, Debug)]
+pub struct Moore<O> {
+    pub next: NextElems,
+    pub outputs: TypedVec<State, O>,
+    pub initial: State,
+}
+
+impl<O: Clone + Eq + Hash> Moore<O> {
+    // There is one state per output.
+    pub fn from_transitions(
+        outputs: Vec<O>,
+        initial: State,
+        transitions: impl IntoIterator<Item = (State, Symbol, State)>,
+    ) -> Result<Self, String> {
+        let states = check_state_count(outputs.len())?;
+        let mut next = NextElems::new();
+        next.try_reserve_exact(states)
+            .map_err(|_| format!("cannot allocate {} states", states))?;
+        next.resize_with(states, HashMap::new);
+
+        for (state, symbol, next_state) in transitions {
+            let state = check_state(state, states)?;
+            let next_state = check_state(next_state, states)?;
+            if next[state].insert(symbol, next_state).is_some() {
+                return Err(format!(
+                    "nondeterministic transition from {} on {}",
+                    state, symbol
+                ));
+            }
+        }
+
+        let initial = check_state(initial, states)?;
+        let mut typed_outputs = TypedVec::new();
+        *typed_outputs = outputs;
+
+        Ok(Self {
+            next,
+            outputs: typed_outputs,
+            initial,
+        })
+    }
+
+    // Returns the output of the initial state followed by the output of every state reached.
+    // Reading stops at the first symbol without a transition.
+    pub fn run(&self, input: &[Symbol]) -> Vec<O> {
+        let mut state = self.initial;
+        let mut ret = vec![self.outputs[state].clone()];
+
+        for symbol in input {
+            let Some(&next_state) = self.next[state].get(symbol) else {
+                break;
+            };
+            state = next_state;
+            ret.push(self.outputs[state].clone());
+        }
+
+        ret
+    }
+
+    // Merges states with the same output whose successors are pairwise equivalent.
+    pub fn minimize(self) -> Self {
+        let signature = |blocks: &TypedVec<State, usize>, state: State| {
+            let mut transitions: Vec<(Symbol, usize)> = self.next[state]
+                .iter()
+                .map(|(&symbol, &next_state)| (symbol, blocks[next_state]))
+                .collect();
+            transitions.sort();
+            (&self.outputs[state], transitions)
+        };
+        let blocks = refine(self.next.len(), signature);
+
+        let mut next = NextElems::new();
+        let mut outputs: TypedVec<State, O> = TypedVec::new();
+        for (state, dict) in self.next.iter() {
+            if blocks[state] < next.len() {
+                continue;
+            }
+            next.push(
+                dict.iter()
+                    .map(|(&symbol, &next_state)| (symbol, blocks[next_state].into()))
+                    .collect(),
+            );
+            outputs.push(self.outputs[state].clone());
+        }
+
+        Self {
+            next,
+            outputs,
+            initial: blocks[self.initial].into(),
+        }
+    }
+}
+
+impl<O: Display> Moore<O> {
+    // States are labelled "state/out", unless `options` gives them another label.
+    pub fn write_dot<W: io::Write>(&self, w: W, options: &DotOptions) -> io::Result<()> {
+        let mut options = options.clone();
+        for (state, output) in self.outputs.iter() {
+            options
+                .state_labels
+                .entry(state)
+                .or_insert_with(|| format!("{}/{}", state, output));
+        }
+
+        dot::write_dot(
+            w,
+            self.next.len(),
+            iter::once(self.initial),
+            iter::empty(),
+            self.next.iter().flat_map(|(state, dict)| {
+                dict.iter()
+                    .map(move |(&symbol, &next_state)| (state, symbol, next_state))
+            }),
+            &options,
+        )
+    }
+
+    pub fn print_graphviz(&self) {
+        self.write_dot(io::stdout().lock(), &DotOptions::default())
+            .unwrap();
+    }
+}